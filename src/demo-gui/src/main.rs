@@ -163,7 +163,7 @@ impl gui::Capturer for World {
         let start = OffsetDateTime::now_utc();
         match self.screenshotter.capture() {
             Ok(screenshot) => {
-                self.screenshot = Some(screenshot);
+                self.screenshot = Some(screenshot.clone());
 
                 self.sample = Some(self.sampler.sample(self.screenshot.as_ref().unwrap()));
             }
@@ -0,0 +1,289 @@
+use dxgcap::BGRA8;
+use serde::{Deserialize, Serialize};
+
+use crate::color::{linear_to_srgb, luma, srgb_to_linear};
+
+/// Color space in which the pixels of a region are averaged together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AveragingMode {
+    /// Average the sRGB-encoded values directly.  This is the cheapest option, but regions mixing
+    /// bright and dark pixels come out darker than they appear on screen.
+    #[default]
+    Srgb,
+    /// Decode each pixel to linear light before averaging and re-encode the result, so that the
+    /// averaged color matches what the eye sees.
+    Linear,
+}
+
+/// Ignore dark pixels when averaging a region, so that small highlights in otherwise dark scenes
+/// still produce a color.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DarkRejection {
+    /// Luminance (0 to 255) below which a pixel is ignored.
+    pub threshold: u8,
+    /// Fraction (0 to 1) of a region's pixels that must be dark for the region to be output as
+    /// black instead of the average of its remaining pixels.
+    pub max_dark_fraction: f32,
+}
+
+/// Settings controlling how the pixels of a region are averaged together.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AverageOptions {
+    pub mode: AveragingMode,
+    pub dark_rejection: Option<DarkRejection>,
+    /// Weight each pixel by its luminance, so that brighter pixels contribute more to the color.
+    pub luminance_weighted: bool,
+}
+
+/// Compute the average color of a set of pixels.
+pub(crate) fn average<I>(pixels: I, options: &AverageOptions) -> BGRA8
+where
+    I: Iterator<Item = BGRA8>,
+{
+    let mut channels = [
+        RunningAverage::default(),
+        RunningAverage::default(),
+        RunningAverage::default(),
+    ];
+    let mut alpha = RunningAverage::default();
+    let mut total = 0usize;
+    let mut dark = 0usize;
+
+    for px in pixels {
+        total += 1;
+        alpha = alpha.push(px.a as f32, 1f32);
+
+        let luma = luma(&px);
+        if let Some(rejection) = options.dark_rejection
+            && luma * (u8::MAX as f32) < rejection.threshold as f32
+        {
+            dark += 1;
+            continue;
+        }
+
+        let weight = if options.luminance_weighted {
+            luma
+        } else {
+            1f32
+        };
+        let values = match options.mode {
+            AveragingMode::Srgb => [px.r as f32, px.g as f32, px.b as f32],
+            AveragingMode::Linear => [
+                srgb_to_linear(px.r),
+                srgb_to_linear(px.g),
+                srgb_to_linear(px.b),
+            ],
+        };
+        channels = [
+            channels[0].push(values[0], weight),
+            channels[1].push(values[1], weight),
+            channels[2].push(values[2], weight),
+        ];
+    }
+
+    let a = alpha.get() as u8;
+    if let Some(rejection) = options.dark_rejection
+        && total > 0
+        && dark as f32 / total as f32 > rejection.max_dark_fraction
+    {
+        return BGRA8 {
+            b: 0,
+            g: 0,
+            r: 0,
+            a,
+        };
+    }
+
+    let (r, g, b) = match options.mode {
+        AveragingMode::Srgb => (
+            channels[0].get() as u8,
+            channels[1].get() as u8,
+            channels[2].get() as u8,
+        ),
+        AveragingMode::Linear => (
+            linear_to_srgb(channels[0].get()),
+            linear_to_srgb(channels[1].get()),
+            linear_to_srgb(channels[2].get()),
+        ),
+    };
+    BGRA8 { b, g, r, a }
+}
+
+#[derive(Clone, Copy)]
+struct RunningAverage {
+    avg: f32,
+    weight: f32,
+}
+
+impl Default for RunningAverage {
+    fn default() -> Self {
+        RunningAverage {
+            avg: 0f32,
+            weight: 0f32,
+        }
+    }
+}
+
+impl RunningAverage {
+    fn push(mut self, value: f32, weight: f32) -> Self {
+        if weight > 0f32 {
+            self.avg = ((self.avg * self.weight) + value * weight) / (self.weight + weight);
+            self.weight += weight;
+        }
+
+        self
+    }
+
+    fn get(&self) -> f32 {
+        self.avg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(mode: AveragingMode) -> AverageOptions {
+        AverageOptions {
+            mode,
+            ..AverageOptions::default()
+        }
+    }
+
+    fn px(r: u8, g: u8, b: u8) -> BGRA8 {
+        BGRA8 { b, g, r, a: 255 }
+    }
+
+    fn region(colors: &[(BGRA8, usize)]) -> Vec<BGRA8> {
+        colors
+            .iter()
+            .flat_map(|(color, count)| std::iter::repeat_n(*color, *count))
+            .collect()
+    }
+
+    fn assert_color(actual: BGRA8, r: u8, g: u8, b: u8) {
+        let close = |a: u8, e: u8| a.abs_diff(e) <= 1;
+        assert!(
+            close(actual.r, r) && close(actual.g, g) && close(actual.b, b),
+            "expected ({r}, {g}, {b}), got ({}, {}, {})",
+            actual.r,
+            actual.g,
+            actual.b
+        );
+    }
+
+    #[test]
+    fn srgb_half_black_half_white() {
+        let pixels = region(&[(px(0, 0, 0), 50), (px(255, 255, 255), 50)]);
+        assert_color(
+            average(pixels.into_iter(), &options(AveragingMode::Srgb)),
+            127,
+            127,
+            127,
+        );
+    }
+
+    #[test]
+    fn linear_half_black_half_white() {
+        let pixels = region(&[(px(0, 0, 0), 50), (px(255, 255, 255), 50)]);
+        assert_color(
+            average(pixels.into_iter(), &options(AveragingMode::Linear)),
+            188,
+            188,
+            188,
+        );
+    }
+
+    #[test]
+    fn linear_quarter_white() {
+        let pixels = region(&[(px(0, 0, 0), 75), (px(255, 255, 255), 25)]);
+        assert_color(
+            average(pixels.into_iter(), &options(AveragingMode::Linear)),
+            137,
+            137,
+            137,
+        );
+    }
+
+    #[test]
+    fn linear_red_green_mix() {
+        let pixels = region(&[(px(255, 0, 0), 10), (px(0, 255, 0), 10)]);
+        assert_color(
+            average(pixels.into_iter(), &options(AveragingMode::Linear)),
+            188,
+            188,
+            0,
+        );
+    }
+
+    #[test]
+    fn linear_uniform_region_is_unchanged() {
+        let pixels = region(&[(px(12, 128, 240), 16)]);
+        assert_color(
+            average(pixels.into_iter(), &options(AveragingMode::Linear)),
+            12,
+            128,
+            240,
+        );
+    }
+
+    #[test]
+    fn alpha_is_averaged_directly() {
+        let pixels = vec![
+            BGRA8 {
+                b: 0,
+                g: 0,
+                r: 0,
+                a: 0,
+            },
+            BGRA8 {
+                b: 0,
+                g: 0,
+                r: 0,
+                a: 200,
+            },
+        ];
+        assert_eq!(
+            average(pixels.into_iter(), &options(AveragingMode::Linear)).a,
+            100
+        );
+    }
+
+    #[test]
+    fn dark_rejection_keeps_small_highlight() {
+        let pixels = region(&[(px(0, 0, 0), 90), (px(200, 40, 40), 10)]);
+        let options = AverageOptions {
+            dark_rejection: Some(DarkRejection {
+                threshold: 16,
+                max_dark_fraction: 0.95,
+            }),
+            ..AverageOptions::default()
+        };
+        assert_color(average(pixels.into_iter(), &options), 200, 40, 40);
+    }
+
+    #[test]
+    fn dark_rejection_falls_back_to_black() {
+        let pixels = region(&[(px(0, 0, 0), 98), (px(200, 40, 40), 2)]);
+        let options = AverageOptions {
+            dark_rejection: Some(DarkRejection {
+                threshold: 16,
+                max_dark_fraction: 0.95,
+            }),
+            ..AverageOptions::default()
+        };
+        assert_color(average(pixels.into_iter(), &options), 0, 0, 0);
+    }
+
+    #[test]
+    fn luminance_weighting_favors_bright_pixels() {
+        let pixels = region(&[(px(255, 255, 255), 1), (px(0, 0, 64), 1)]);
+        let options = AverageOptions {
+            luminance_weighted: true,
+            ..AverageOptions::default()
+        };
+        let avg = average(pixels.into_iter(), &options);
+        assert!(avg.r > 240 && avg.b > avg.r, "got {avg:?}");
+    }
+}
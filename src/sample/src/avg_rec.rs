@@ -1,15 +1,44 @@
-use dxgcap::BGRA8;
 use ls_screenshot::Screenshot;
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use tracing::{info, info_span};
 
+use crate::average::{AverageOptions, AveragingMode, DarkRejection};
 use crate::core::{Sample, Sampler};
+use crate::letterbox::{LetterboxDetection, LetterboxDetector};
+use crate::mask::ExclusionMask;
+use crate::region::{Region, RegionAverager};
+
+/// Size along one dimension of the screen, given either in pixels or as a fraction of the screen's
+/// size in that dimension so that it scales with the resolution of the display.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Extent {
+    Pixels(usize),
+    Fraction(f32),
+}
+
+impl Extent {
+    /// Get the size in pixels for a screen dimension of `total_px` pixels.
+    pub fn resolve(&self, total_px: usize) -> usize {
+        match self {
+            Extent::Pixels(px) => (*px).min(total_px),
+            Extent::Fraction(fraction) => {
+                ((fraction * total_px as f32).round().max(0f32) as usize).min(total_px)
+            }
+        }
+    }
+}
 
 /// Sampler that outputs the average color of each rectangular region of the edge of the screen.
 pub struct AvgRectangleSampler {
     width: usize,
     height: usize,
-    region_depth_px: usize,
+    horizontal_depth: Extent,
+    vertical_depth: Extent,
+    corner_width: Option<Extent>,
+    corner_height: Option<Extent>,
+    averager: RegionAverager,
+    letterbox: Option<LetterboxDetector>,
     regions: Option<Vec<Region>>,
 }
 
@@ -22,77 +51,161 @@ impl AvgRectangleSampler {
         Self {
             width,
             height,
-            region_depth_px,
+            horizontal_depth: Extent::Pixels(region_depth_px),
+            vertical_depth: Extent::Pixels(region_depth_px),
+            corner_width: None,
+            corner_height: None,
+            averager: RegionAverager::new(),
+            letterbox: None,
             regions: None,
         }
     }
 
-    fn gen_regions(
-        num_x: usize,
-        num_y: usize,
-        depth_px: usize,
-        img_width_px: usize,
-        img_height_px: usize,
-    ) -> Vec<Region> {
+    /// Set how far the regions along the top and bottom edges (`horizontal`) and along the left and
+    /// right edges (`vertical`) extend into the screen.  Fractional depths are relative to the
+    /// screen height and width, respectively.
+    pub fn with_depths(mut self, horizontal: Extent, vertical: Extent) -> Self {
+        self.horizontal_depth = horizontal;
+        self.vertical_depth = vertical;
+        self
+    }
+
+    /// Set the size of the corner regions, rather than sizing them the same as the other regions
+    /// along each edge.  Fractional sizes are relative to the screen width and height.
+    pub fn with_corner_size(mut self, width: Extent, height: Extent) -> Self {
+        self.corner_width = Some(width);
+        self.corner_height = Some(height);
+        self
+    }
+
+    /// Set the color space that the pixels of each region are averaged in.
+    pub fn with_averaging_mode(mut self, mode: AveragingMode) -> Self {
+        self.averager.options.mode = mode;
+        self
+    }
+
+    /// Ignore pixels darker than the rejection threshold when averaging each region.
+    pub fn with_dark_rejection(mut self, rejection: DarkRejection) -> Self {
+        self.averager.options.dark_rejection = Some(rejection);
+        self
+    }
+
+    /// Weight each pixel by its luminance when averaging each region.
+    pub fn with_luminance_weighting(mut self, enabled: bool) -> Self {
+        self.averager.options.luminance_weighted = enabled;
+        self
+    }
+
+    /// Only sample every `stride`th pixel across and down each region, trading accuracy for speed.
+    /// The sampled pixels are shifted randomly each frame to avoid aliasing against regular
+    /// patterns on screen.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.averager.set_stride(stride);
+        self
+    }
+
+    /// Replace the settings controlling how the pixels of each region are averaged together.
+    pub fn set_averaging(&mut self, options: AverageOptions) {
+        self.averager.options = options;
+    }
+
+    /// Replace the stride that pixels are sampled at, as set by `with_stride`.
+    pub fn set_stride(&mut self, stride: usize) {
+        self.averager.set_stride(stride);
+    }
+
+    /// Skip the areas of the screen covered by `mask` when averaging each region.
+    pub fn with_mask(mut self, mask: ExclusionMask) -> Self {
+        self.set_mask(Some(mask));
+        self
+    }
+
+    /// Replace the exclusion mask applied to the screen, or remove it if `None`.
+    pub fn set_mask(&mut self, mask: Option<ExclusionMask>) {
+        self.averager.set_mask(mask);
+    }
+
+    /// Detect black bars above and below the picture, moving the regions along the top and bottom
+    /// edges inwards to sample the edge of the picture instead of the bars.
+    pub fn with_letterbox_detection(mut self, detection: LetterboxDetection) -> Self {
+        self.set_letterbox_detection(Some(detection));
+        self
+    }
+
+    /// Replace the letterbox detection settings, or stop detecting bars if `None`.
+    pub fn set_letterbox_detection(&mut self, detection: Option<LetterboxDetection>) {
+        self.letterbox = detection.map(LetterboxDetector::new);
+        self.regions = None;
+    }
+
+    /// Generate the sampling regions for a screen of the given size, leaving out bars of
+    /// `bar_px` pixels at the top and bottom of the screen.
+    fn gen_regions(&self, img_width_px: usize, img_height_px: usize, bar_px: usize) -> Vec<Region> {
         let span = info_span!("Generating sampling regions");
         let _guard = span.enter();
         let start = OffsetDateTime::now_utc();
 
-        let cell_width_px = img_width_px / num_x;
-        let cell_height_px = img_height_px / num_y;
+        let top_px = bar_px.min(img_height_px / 2);
+        let bottom_px = img_height_px - top_px;
+        let content_height_px = bottom_px - top_px;
+
+        let num_x = self.width;
+        let num_y = self.height;
+        let horizontal_depth_px = self.horizontal_depth.resolve(content_height_px);
+        let vertical_depth_px = self.vertical_depth.resolve(img_width_px);
+        let corner_width_px = self
+            .corner_width
+            .map_or(img_width_px / num_x, |extent| extent.resolve(img_width_px));
+        let corner_height_px = self
+            .corner_height
+            .map_or(content_height_px / num_y, |extent| {
+                extent.resolve(content_height_px)
+            });
+
+        let xs = AvgRectangleSampler::edge_bounds(num_x, corner_width_px, img_width_px);
+        let ys: Vec<usize> =
+            AvgRectangleSampler::edge_bounds(num_y, corner_height_px, content_height_px)
+                .into_iter()
+                .map(|y| y + top_px)
+                .collect();
 
         let mut regions: Vec<Region> = Vec::with_capacity(num_x * 2 + (num_y - 2) * 2);
 
-        regions.push(Region::new(0, cell_width_px, 0, cell_height_px));
+        regions.push(Region::new(xs[0], xs[1], ys[0], ys[1]));
         for i in 1..num_x - 1 {
             regions.push(Region::new(
-                cell_height_px * i,
-                cell_width_px * (i + 1),
-                0,
-                depth_px,
+                xs[i],
+                xs[i + 1],
+                top_px,
+                top_px + horizontal_depth_px,
             ));
         }
-        regions.push(Region::new(
-            cell_width_px * (num_x - 1),
-            img_width_px,
-            0,
-            cell_height_px,
-        ));
+        regions.push(Region::new(xs[num_x - 1], xs[num_x], ys[0], ys[1]));
         for i in 1..num_y - 1 {
             regions.push(Region::new(
-                img_width_px - depth_px,
+                img_width_px - vertical_depth_px,
                 img_width_px,
-                cell_height_px * i,
-                cell_height_px * (i + 1),
+                ys[i],
+                ys[i + 1],
             ));
         }
         regions.push(Region::new(
-            cell_width_px * (num_x - 1),
-            img_width_px,
-            cell_height_px * (num_y - 1),
-            img_height_px,
+            xs[num_x - 1],
+            xs[num_x],
+            ys[num_y - 1],
+            ys[num_y],
         ));
         for i in (1..num_x - 1).rev() {
             regions.push(Region::new(
-                cell_height_px * i,
-                cell_width_px * (i + 1),
-                img_height_px - depth_px,
-                img_height_px,
+                xs[i],
+                xs[i + 1],
+                bottom_px - horizontal_depth_px,
+                bottom_px,
             ));
         }
-        regions.push(Region::new(
-            0,
-            cell_width_px,
-            cell_height_px * (num_y - 1),
-            img_height_px,
-        ));
+        regions.push(Region::new(xs[0], xs[1], ys[num_y - 1], ys[num_y]));
         for i in (1..num_y - 1).rev() {
-            regions.push(Region::new(
-                0,
-                depth_px,
-                cell_height_px * i,
-                cell_height_px * (i + 1),
-            ));
+            regions.push(Region::new(0, vertical_depth_px, ys[i], ys[i + 1]));
         }
 
         let end = OffsetDateTime::now_utc();
@@ -101,134 +214,102 @@ impl AvgRectangleSampler {
 
         regions
     }
+
+    /// Compute the `count + 1` boundaries between the cells along one edge of the screen, where
+    /// the first and last cells are corners of size `corner_px` and the remaining cells evenly
+    /// divide the space between them.
+    fn edge_bounds(count: usize, corner_px: usize, total_px: usize) -> Vec<usize> {
+        let interior_cells = (count - 2).max(1);
+        let interior_px = total_px.saturating_sub(corner_px * 2);
+
+        let mut bounds = Vec::with_capacity(count + 1);
+        bounds.push(0);
+        for i in 0..count - 1 {
+            bounds.push(corner_px + interior_px * i / interior_cells);
+        }
+        bounds.push(total_px);
+        bounds
+    }
 }
 
 impl Sampler for AvgRectangleSampler {
     fn sample(&mut self, screenshot: &Screenshot) -> Sample {
+        if let Some(letterbox) = &mut self.letterbox
+            && letterbox.update(screenshot)
+        {
+            self.regions = None;
+        }
+
         if self.regions.is_none() {
-            self.regions = Some(AvgRectangleSampler::gen_regions(
-                self.width,
-                self.height,
-                self.region_depth_px,
-                screenshot.width,
-                screenshot.height,
-            ));
+            let bar_px = self
+                .letterbox
+                .as_ref()
+                .map_or(0, |letterbox| letterbox.bar_px());
+            self.regions = Some(self.gen_regions(screenshot.width, screenshot.height, bar_px));
         }
 
-        let regions = self.regions.as_ref().unwrap();
-        let pixels = regions
-            .iter()
-            .map(|region| {
-                let sums = region
-                    .iter()
-                    .map(|(x, y)| {
-                        let i = y * screenshot.width + x;
-                        let px = screenshot.pixels[i];
-                        (px.r, px.g, px.b, px.a)
-                    })
-                    .fold(
-                        (
-                            RunningAverage::default(),
-                            RunningAverage::default(),
-                            RunningAverage::default(),
-                            RunningAverage::default(),
-                        ),
-                        |accum, elem| {
-                            (
-                                accum.0.push(elem.0),
-                                accum.1.push(elem.1),
-                                accum.2.push(elem.2),
-                                accum.3.push(elem.3),
-                            )
-                        },
-                    );
-                let (r, g, b, a) = (sums.0.get(), sums.1.get(), sums.2.get(), sums.3.get());
-                BGRA8 { b, g, r, a }
-            })
-            .collect();
-
-        Sample::new(pixels, self.width, self.height)
+        let pixels = self
+            .averager
+            .average(screenshot, self.regions.as_ref().unwrap());
+        Sample::new(pixels, self.width, self.height).with_info(screenshot.info.clone())
     }
 }
 
-struct RunningAverage {
-    avg: f32,
-    count: usize,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+    use crate::rect::NormRect;
+    use ls_screenshot::FrameInfo;
 
-impl Default for RunningAverage {
-    fn default() -> Self {
-        RunningAverage {
-            avg: 0f32,
-            count: 0,
+    fn screenshot(info: FrameInfo) -> Screenshot {
+        Screenshot {
+            pixels: vec![rgb(200, 100, 50); 32 * 24],
+            width: 32,
+            height: 24,
+            info,
         }
     }
-}
 
-impl RunningAverage {
-    fn push(mut self, value: u8) -> Self {
-        self.avg = ((self.avg * self.count as f32) + value as f32) / (self.count as f32 + 1f32);
-        self.count += 1;
-
-        self
+    #[test]
+    fn carries_frame_info() {
+        let mut sampler = AvgRectangleSampler::new(4, 3, 4);
+        let info = FrameInfo {
+            unchanged: true,
+            ..FrameInfo::new("test", 7)
+        };
+        let sample = sampler.sample(&screenshot(info.clone()));
+        assert_eq!(sample.info.frame_id, 7);
+        assert_eq!(sample.info.source, "test");
+        assert_eq!(sample.info.captured, info.captured);
+        assert!(sample.info.unchanged);
     }
 
-    fn get(&self) -> u8 {
-        self.avg as u8
+    #[test]
+    fn new_frame_with_same_content_is_not_unchanged() {
+        let mut sampler = AvgRectangleSampler::new(4, 3, 4);
+        sampler.sample(&screenshot(FrameInfo::new("test", 0)));
+        let sample = sampler.sample(&screenshot(FrameInfo::new("test", 1)));
+        assert_eq!(sample.info.frame_id, 1);
+        assert!(!sample.info.unchanged);
     }
-}
 
-#[derive(Debug)]
-struct Region {
-    start_x: usize,
-    end_x: usize,
-    start_y: usize,
-    end_y: usize,
-}
-
-impl Region {
-    fn new(start_x: usize, end_x: usize, start_y: usize, end_y: usize) -> Region {
-        Region {
-            start_x,
-            end_x,
-            start_y,
-            end_y,
-        }
-    }
-
-    fn iter(&self) -> RegionIter {
-        RegionIter {
-            region: self,
-            x: self.start_x,
-            y: self.start_y,
+    #[test]
+    fn skips_masked_pixels() {
+        // the top two rows are a red logo, masked out of the otherwise orange screen
+        let mut shot = screenshot(FrameInfo::new("test", 0));
+        for px in &mut shot.pixels[..32 * 2] {
+            *px = rgb(255, 0, 0);
         }
-    }
-}
+        let mask = ExclusionMask::new(vec![NormRect::new(0f32, 0f32, 1f32, 2f32 / 24f32)], None);
 
-#[derive(Debug)]
-struct RegionIter<'a> {
-    region: &'a Region,
-    x: usize,
-    y: usize,
-}
+        let mut unmasked = AvgRectangleSampler::new(4, 3, 4);
+        assert!(unmasked.sample(&shot).pixels[1].r > 200);
 
-impl Iterator for RegionIter<'_> {
-    type Item = (usize, usize);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.x + 1 >= self.region.end_x {
-            if self.y + 1 >= self.region.end_y {
-                None
-            } else {
-                let out = (self.x, self.y);
-                self.x = self.region.start_x;
-                self.y += 1;
-                Some(out)
-            }
-        } else {
-            let out = (self.x, self.y);
-            self.x += 1;
-            Some(out)
+        let mut masked = AvgRectangleSampler::new(4, 3, 4).with_mask(mask);
+        let sample = masked.sample(&shot);
+        for px in &sample.pixels {
+            assert_eq!((px.r, px.g, px.b), (200, 100, 50));
         }
     }
 }
@@ -0,0 +1,188 @@
+use dxgcap::BGRA8;
+use std::sync::LazyLock;
+
+/// Number of steps used to quantize linear light when encoding back to sRGB.
+const LINEAR_STEPS: usize = 4095;
+
+/// Linear light intensity (0 to 1) for each 8-bit sRGB-encoded value.
+static SRGB_TO_LINEAR: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    let mut table = [0f32; 256];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = decode_srgb(i as f32 / u8::MAX as f32);
+    }
+    table
+});
+
+/// 8-bit sRGB-encoded value for each quantized step of linear light intensity.
+static LINEAR_TO_SRGB: LazyLock<[u8; LINEAR_STEPS + 1]> = LazyLock::new(|| {
+    let mut table = [0u8; LINEAR_STEPS + 1];
+    for (i, value) in table.iter_mut().enumerate() {
+        let v = i as f32 / LINEAR_STEPS as f32;
+        let encoded = if v <= 0.0031308 {
+            v * 12.92
+        } else {
+            1.055 * v.powf(1f32 / 2.4) - 0.055
+        };
+        *value = (encoded * u8::MAX as f32).round() as u8;
+    }
+    table
+});
+
+/// Decode an sRGB-encoded channel value (0 to 1) to linear light intensity (0 to 1).
+fn decode_srgb(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Decode an sRGB-encoded channel value to linear light intensity (0 to 1).
+pub fn srgb_to_linear(value: u8) -> f32 {
+    SRGB_TO_LINEAR[value as usize]
+}
+
+/// Encode a linear light intensity (0 to 1) to an sRGB channel value.
+pub fn linear_to_srgb(value: f32) -> u8 {
+    LINEAR_TO_SRGB[(value.clamp(0f32, 1f32) * LINEAR_STEPS as f32).round() as usize]
+}
+
+/// Convert an `[r, g, b]` color with channels from 0 to 1 into hue (degrees from 0 to 360),
+/// saturation (0 to 1), and value (0 to 1).
+pub fn rgb_to_hsv(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0f32 {
+        0f32
+    } else if max == r {
+        60f32 * ((g - b) / delta).rem_euclid(6f32)
+    } else if max == g {
+        60f32 * ((b - r) / delta + 2f32)
+    } else {
+        60f32 * ((r - g) / delta + 4f32)
+    };
+    let saturation = if max == 0f32 { 0f32 } else { delta / max };
+
+    [hue, saturation, max]
+}
+
+/// Convert a hue (degrees), saturation (0 to 1), and value (0 to 1) color into `[r, g, b]` with
+/// channels from 0 to 1.
+pub fn hsv_to_rgb(hsv: [f32; 3]) -> [f32; 3] {
+    let [hue, saturation, value] = hsv;
+    let chroma = value * saturation;
+    let sector = hue.rem_euclid(360f32) / 60f32;
+    let x = chroma * (1f32 - (sector.rem_euclid(2f32) - 1f32).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0f32),
+        1 => (x, chroma, 0f32),
+        2 => (0f32, chroma, x),
+        3 => (0f32, x, chroma),
+        4 => (x, 0f32, chroma),
+        _ => (chroma, 0f32, x),
+    };
+    let m = value - chroma;
+
+    [r + m, g + m, b + m]
+}
+
+/// Convert an sRGB-encoded `[r, g, b]` color with channels from 0 to 1 into CIE L*a*b*, relative
+/// to the D65 white point, with L* from 0 to 100.
+pub fn rgb_to_lab(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(|channel| decode_srgb(channel.clamp(0f32, 1f32)));
+    // linear sRGB to XYZ, normalized by the D65 white point
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 216f32 / 24389f32 {
+            t.cbrt()
+        } else {
+            (24389f32 / 27f32 * t + 16f32) / 116f32
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116f32 * fy - 16f32, 500f32 * (fx - fy), 200f32 * (fy - fz)]
+}
+
+/// Perceptual difference between two L*a*b* colors (CIE76 delta E), where a difference of about
+/// 2.3 is just noticeable.
+pub fn delta_e(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Perceived brightness (0 to 1) of an sRGB-encoded pixel, using the Rec. 709 luma coefficients.
+pub fn luma(px: &BGRA8) -> f32 {
+    (0.2126 * px.r as f32 + 0.7152 * px.g as f32 + 0.0722 * px.b as f32) / u8::MAX as f32
+}
+
+/// Create a fully opaque pixel from red, green, and blue values.
+pub const fn rgb(r: u8, g: u8, b: u8) -> BGRA8 {
+    BGRA8 {
+        b,
+        g,
+        r,
+        a: u8::MAX,
+    }
+}
+
+/// Scale the red, green, and blue channels of a pixel by `factor`.
+pub fn scale(px: &BGRA8, factor: f32) -> BGRA8 {
+    let apply = |value: u8| (value as f32 * factor).round().clamp(0f32, u8::MAX as f32) as u8;
+    BGRA8 {
+        b: apply(px.b),
+        g: apply(px.g),
+        r: apply(px.r),
+        a: px.a,
+    }
+}
+
+/// Blend between two pixels, where a `t` of 0 gives `from` and 1 gives `to`.
+pub fn mix(from: &BGRA8, to: &BGRA8, t: f32) -> BGRA8 {
+    let apply = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    BGRA8 {
+        b: apply(from.b, to.b),
+        g: apply(from.g, to.g),
+        r: apply(from.r, to.r),
+        a: apply(from.a, to.a),
+    }
+}
+
+/// Approximate `[r, g, b]` color (0 to 1) of a black body at `kelvin`, from Tanner Helland's fit
+/// of the CIE 1964 color matching data.  Valid from 1000K to 40000K.
+fn black_body(kelvin: f32) -> [f32; 3] {
+    let t = kelvin.clamp(1000f32, 40_000f32) / 100f32;
+    let r = if t <= 66f32 {
+        255f32
+    } else {
+        329.69873 * (t - 60f32).powf(-0.13320476)
+    };
+    let g = if t <= 66f32 {
+        99.4708 * t.ln() - 161.11957
+    } else {
+        288.12217 * (t - 60f32).powf(-0.075514846)
+    };
+    let b = if t >= 66f32 {
+        255f32
+    } else if t <= 19f32 {
+        0f32
+    } else {
+        138.51773 * (t - 10f32).ln() - 305.0448
+    };
+    [r, g, b].map(|channel| channel.clamp(0f32, 255f32) / 255f32)
+}
+
+/// Gains (0 to 1) to apply to the red, green, and blue channels to shift white from the 6500K
+/// white point of the screen to a color temperature of `kelvin`.  The largest gain is always 1,
+/// so shifting the temperature never brightens any channel.
+pub fn kelvin_to_gains(kelvin: f32) -> [f32; 3] {
+    let target = black_body(kelvin);
+    let reference = black_body(6500f32);
+    let gains = [0, 1, 2].map(|c| target[c] / reference[c].max(f32::EPSILON));
+    let max = gains.iter().cloned().fold(f32::EPSILON, f32::max);
+    gains.map(|gain| gain / max)
+}
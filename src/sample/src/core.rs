@@ -1,5 +1,11 @@
 use dxgcap::BGRA8;
-use ls_screenshot::Screenshot;
+use ls_screenshot::{FrameInfo, Screenshot};
+
+/// Source recorded for samples that weren't given any frame information.
+const UNKNOWN_SOURCE: &str = "unknown";
+
+/// Factor between an 8 bit channel value and the matching 16 bit value in `Sample::wide`.
+const WIDE_SCALE: f32 = 257f32;
 
 /// An edge sampling of the colors of a screenshot, specified as a circle of pixels bordering the
 /// screen.
@@ -9,6 +15,13 @@ pub struct Sample {
     pub pixels: Vec<BGRA8>,
     pub width: usize,
     pub height: usize,
+    /// Higher precision `[r, g, b]` colors for each LED, with 16 bits per channel, kept by stages
+    /// that would otherwise lose precision when rounding to 8 bits.  When present, `pixels` holds
+    /// these colors rounded to 8 bits.
+    pub wide: Option<Vec<[u16; 3]>>,
+    /// Information about the frame this sample was taken from, kept through processing so that
+    /// the output can be traced back to the capture.
+    pub info: FrameInfo,
 }
 
 impl Sample {
@@ -17,10 +30,111 @@ impl Sample {
             pixels,
             width,
             height,
+            wide: None,
+            info: FrameInfo::new(UNKNOWN_SOURCE, 0),
+        }
+    }
+
+    /// Set the information about the frame this sample was taken from.
+    pub fn with_info(mut self, info: FrameInfo) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Get the `[r, g, b]` color of each LED with channels from 0 to 255, using the higher
+    /// precision colors if available.
+    pub fn colors(&self) -> Vec<[f32; 3]> {
+        match &self.wide {
+            Some(wide) => wide
+                .iter()
+                .map(|c| c.map(|channel| channel as f32 / WIDE_SCALE))
+                .collect(),
+            None => self
+                .pixels
+                .iter()
+                .map(|px| [px.r as f32, px.g as f32, px.b as f32])
+                .collect(),
+        }
+    }
+
+    /// Replace the `[r, g, b]` color of each LED with channels from 0 to 255, keeping them at
+    /// higher precision as well as rounding them into `pixels`.
+    pub fn set_colors(&mut self, colors: &[[f32; 3]]) {
+        let mut wide = Vec::with_capacity(colors.len());
+        for (px, color) in self.pixels.iter_mut().zip(colors.iter()) {
+            let color = color.map(|channel| channel.clamp(0f32, u8::MAX as f32));
+            px.r = color[0].round() as u8;
+            px.g = color[1].round() as u8;
+            px.b = color[2].round() as u8;
+            wide.push(color.map(|channel| (channel * WIDE_SCALE).round() as u16));
         }
+        self.wide = Some(wide);
+    }
+
+    /// Apply `f` to the `[r, g, b]` color of each LED, with channels from 0 to 255, without losing
+    /// precision between stages.
+    pub fn map_rgb<F: FnMut([f32; 3]) -> [f32; 3]>(&mut self, f: F) {
+        let colors: Vec<[f32; 3]> = self.colors().into_iter().map(f).collect();
+        self.set_colors(&colors);
+    }
+
+    /// Get the arrangement of LEDs this sample was taken for.
+    pub fn layout(&self) -> Layout {
+        Layout::new(self.width, self.height)
+    }
+}
+
+/// Arrangement of LEDs in a ring around the edge of the screen, with `width` LEDs across and
+/// `height` LEDs down.  The LEDs in each corner are shared by the two edges that meet there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Layout {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    /// Total number of LEDs in the ring.
+    pub fn len(&self) -> usize {
+        self.width * 2 + self.height * 2 - 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Indices of the top-left, top-right, bottom-right, and bottom-left corner LEDs, in that
+    /// order.
+    pub fn corners(&self) -> [usize; 4] {
+        [
+            0,
+            self.width - 1,
+            self.width - 1 + self.height - 1,
+            (self.width - 1) * 2 + self.height - 1,
+        ]
+    }
+
+    /// Number of steps between consecutive corners, for the top, right, bottom, and left edges.
+    pub fn edge_lengths(&self) -> [usize; 4] {
+        [
+            self.width - 1,
+            self.height - 1,
+            self.width - 1,
+            self.height - 1,
+        ]
     }
 }
 
 pub trait Sampler {
     fn sample(&mut self, screenshot: &Screenshot) -> Sample;
 }
+
+/// A processing step applied to each `Sample` after it has been produced by a `Sampler` and before
+/// it is sent to the output.  Stages that build a new `Sample` must carry over the `info` of the
+/// one they were given.
+pub trait Stage {
+    fn process(&mut self, sample: Sample) -> Sample;
+}
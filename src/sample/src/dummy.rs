@@ -1,5 +1,5 @@
 use dxgcap::BGRA8;
-use ls_screenshot::Screenshot;
+use ls_screenshot::{FrameInfo, Screenshot};
 
 use crate::core::{Sample, Sampler};
 
@@ -8,40 +8,56 @@ use crate::core::{Sample, Sampler};
 pub struct DummySampler {
     width: usize,
     height: usize,
+    frame_id: u64,
 }
 
 impl DummySampler {
     pub fn new(width: usize, height: usize) -> Self {
-        Self { width, height }
+        Self {
+            width,
+            height,
+            frame_id: 0,
+        }
     }
 }
 
 impl Sampler for DummySampler {
     fn sample(&mut self, _screenshot: &Screenshot) -> Sample {
         let length = self.width * 2 + self.height * 2 - 4;
-        let min_color = (u8::MAX as f32 * 0.25).floor() as u8;
-        let inc = (u8::MAX - min_color) / length as u8;
+        let min_color = (u8::MAX as f32 * 0.25).floor();
+        let inc = (u8::MAX as f32 - min_color) / length as f32;
         let mut pixels = Vec::with_capacity(length);
         for i in 0..length {
+            let value = (inc * i as f32 + min_color) as u8;
             pixels.push(BGRA8 {
-                b: if i % 3 == 0 {
-                    0
-                } else {
-                    inc * i as u8 + min_color
-                },
-                g: if i % 3 == 1 {
-                    0
-                } else {
-                    inc * i as u8 + min_color
-                },
-                r: if i % 3 == 2 {
-                    0
-                } else {
-                    inc * i as u8 + min_color
-                },
+                b: if i % 3 == 0 { 0 } else { value },
+                g: if i % 3 == 1 { 0 } else { value },
+                r: if i % 3 == 2 { 0 } else { value },
                 a: 100,
             });
         }
-        Sample::new(pixels, self.width, self.height)
+        let info = FrameInfo::new("dummy", self.frame_id);
+        self.frame_id += 1;
+        Sample::new(pixels, self.width, self.height).with_info(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_ids_count_up() {
+        let screenshot = Screenshot {
+            pixels: Vec::new(),
+            width: 0,
+            height: 0,
+            info: FrameInfo::new("test", 0),
+        };
+        let mut sampler = DummySampler::new(4, 3);
+        let ids: Vec<u64> = (0..3)
+            .map(|_| sampler.sample(&screenshot).info.frame_id)
+            .collect();
+        assert_eq!(ids, vec![0, 1, 2]);
     }
 }
@@ -0,0 +1,74 @@
+use ls_screenshot::{FrameInfo, Screenshot};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+use crate::core::{Layout, Sample, Sampler};
+
+/// Source of colors that animates over time instead of following the screen, such as mood
+/// lighting.
+pub trait Effect {
+    /// Render the effect for the LEDs in `layout`, `time` after the effect was started.
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample;
+
+    /// Provide the most recent sample of the screen, for effects that react to it, or `None` if
+    /// there is none.  Effects that don't use the screen can ignore it.
+    fn set_screen(&mut self, _screen: Option<Sample>) {}
+}
+
+/// Sampler that outputs an `Effect` instead of following the screen, allowing effects to be
+/// selected in place of screen sampling.  If a screen sampler is set, the screen is still sampled
+/// and passed to the effect for it to react to.
+pub struct EffectSampler {
+    layout: Layout,
+    effect: Box<dyn Effect>,
+    screen_sampler: Option<Box<dyn Sampler>>,
+    start: Option<OffsetDateTime>,
+    frame_id: u64,
+}
+
+impl EffectSampler {
+    pub fn new(width: usize, height: usize, effect: Box<dyn Effect>) -> Self {
+        Self {
+            layout: Layout::new(width, height),
+            effect,
+            screen_sampler: None,
+            start: None,
+            frame_id: 0,
+        }
+    }
+
+    /// Switch to a different effect, starting it from the beginning.
+    pub fn set_effect(&mut self, effect: Box<dyn Effect>) {
+        self.effect = effect;
+        self.start = None;
+    }
+
+    /// Sample the screen with `sampler` each frame and pass the result to the effect.
+    pub fn with_screen_sampler(mut self, sampler: Box<dyn Sampler>) -> Self {
+        self.set_screen_sampler(Some(sampler));
+        self
+    }
+
+    /// Replace the sampler used to pass the screen to the effect, or stop passing it if `None`.
+    pub fn set_screen_sampler(&mut self, sampler: Option<Box<dyn Sampler>>) {
+        if sampler.is_none() {
+            self.effect.set_screen(None);
+        }
+        self.screen_sampler = sampler;
+    }
+}
+
+impl Sampler for EffectSampler {
+    fn sample(&mut self, screenshot: &Screenshot) -> Sample {
+        if let Some(sampler) = &mut self.screen_sampler {
+            self.effect.set_screen(Some(sampler.sample(screenshot)));
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let start = *self.start.get_or_insert(now);
+        let time = Duration::try_from(now - start).unwrap_or_default();
+        let info = FrameInfo::new("effect", self.frame_id);
+        self.frame_id += 1;
+        self.effect.render(&self.layout, time).with_info(info)
+    }
+}
@@ -0,0 +1,365 @@
+use dxgcap::BGRA8;
+use hound::{SampleFormat, WavReader};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::color::{hsv_to_rgb, rgb, scale};
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Number of audio samples analyzed for each frame.
+const FFT_SIZE: usize = 1024;
+/// Number of frequency bands the spectrum is divided into.
+const NUM_BANDS: usize = 16;
+/// Lowest and highest frequencies covered by the bands, in Hz.
+const MIN_FREQ: f32 = 40f32;
+const MAX_FREQ: f32 = 16_000f32;
+/// Highest frequency considered bass when detecting beats, in Hz.
+const BASS_FREQ: f32 = 150f32;
+/// Range of loudness, in dB below full scale, that is mapped onto LED brightness.
+const DYNAMIC_RANGE_DB: f32 = 60f32;
+/// Rate at which displayed levels fall back after a peak, in full-scale per second.
+const DECAY_RATE: f32 = 2f32;
+/// Number of seconds of bass energy history that beats are detected against.
+const BEAT_HISTORY: f32 = 1f32;
+/// How much louder than average the bass must be to count as a beat.
+const BEAT_THRESHOLD: f32 = 1.4;
+/// Minimum time between beats, in seconds.
+const BEAT_COOLDOWN: f32 = 0.2;
+
+/// How the analyzed audio is shown on the LEDs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioMode {
+    /// Frequency bands spread around the ring, from bass at the first LED to treble at the last,
+    /// each lit by the loudness of its band.
+    Spectrum,
+    /// The ring filled from the first LED in proportion to the overall loudness, shading from green
+    /// to red.
+    VuMeter,
+    /// Every LED flashed with a new color on each detected beat, fading out until the next.
+    Pulse,
+}
+
+/// Effect that reacts to audio read as PCM WAV data from a file or stdin, so that it can be driven
+/// by piping from an audio stack without needing audio hardware.
+pub struct AudioEffect {
+    reader: WavReader<Box<dyn Read>>,
+    mode: AudioMode,
+    frames_read: u64,
+    window: VecDeque<f32>,
+    fft: Arc<dyn Fft<f32>>,
+    levels: [f32; NUM_BANDS],
+    loudness: f32,
+    bass_history: VecDeque<f32>,
+    since_beat: f32,
+    pulse: f32,
+    pulse_hue: f32,
+    last_time: Duration,
+}
+
+impl AudioEffect {
+    /// Create a new effect reading WAV data from `input`.
+    pub fn new(input: Box<dyn Read>, mode: AudioMode) -> anyhow::Result<AudioEffect> {
+        let reader = WavReader::new(input)?;
+        let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+        Ok(AudioEffect {
+            reader,
+            mode,
+            frames_read: 0,
+            window: VecDeque::from(vec![0f32; FFT_SIZE]),
+            fft,
+            levels: [0f32; NUM_BANDS],
+            loudness: 0f32,
+            bass_history: VecDeque::new(),
+            since_beat: BEAT_COOLDOWN,
+            pulse: 0f32,
+            pulse_hue: 0f32,
+            last_time: Duration::ZERO,
+        })
+    }
+
+    /// Create a new effect reading a WAV file.
+    pub fn from_file(path: &Path, mode: AudioMode) -> anyhow::Result<AudioEffect> {
+        AudioEffect::new(Box::new(BufReader::new(File::open(path)?)), mode)
+    }
+
+    /// Create a new effect reading WAV data piped to stdin.
+    pub fn from_stdin(mode: AudioMode) -> anyhow::Result<AudioEffect> {
+        AudioEffect::new(Box::new(BufReader::new(io::stdin())), mode)
+    }
+
+    /// Read the next frame of audio, mixing all channels down to a single value from -1 to 1.
+    /// Returns `None` once the input is exhausted.
+    fn read_frame(&mut self) -> Option<f32> {
+        let spec = self.reader.spec();
+        let mut sum = 0f32;
+        for _ in 0..spec.channels {
+            let value = match spec.sample_format {
+                SampleFormat::Float => self.reader.samples::<f32>().next()?.ok()?,
+                SampleFormat::Int => {
+                    let value = self.reader.samples::<i32>().next()?.ok()?;
+                    value as f32 / (1i64 << (spec.bits_per_sample - 1)) as f32
+                }
+            };
+            sum += value;
+        }
+        self.frames_read += 1;
+        Some(sum / spec.channels as f32)
+    }
+
+    /// Read all audio up to `time`, keeping the most recent samples for analysis.  Returns `false`
+    /// if the input ran out first.
+    fn read_until(&mut self, time: Duration) -> bool {
+        let target = (time.as_secs_f64() * self.reader.spec().sample_rate as f64) as u64;
+        while self.frames_read < target {
+            match self.read_frame() {
+                Some(value) => {
+                    self.window.pop_front();
+                    self.window.push_back(value);
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Analyze the current window of audio, updating the band levels, loudness, and beat state.
+    fn analyze(&mut self, dt: f32) {
+        let sample_rate = self.reader.spec().sample_rate as f32;
+        let mut buffer: Vec<Complex<f32>> = self
+            .window
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let hann = 0.5 - 0.5 * (TAU * i as f32 / (FFT_SIZE - 1) as f32).cos();
+                Complex::new(value * hann, 0f32)
+            })
+            .collect();
+        self.fft.process(&mut buffer);
+
+        // a full scale sine wave peaks at a quarter of the window size once windowed
+        let magnitudes: Vec<f32> = buffer[..FFT_SIZE / 2]
+            .iter()
+            .map(|bin| bin.norm() / (FFT_SIZE as f32 / 4f32))
+            .collect();
+        let bin_width = sample_rate / FFT_SIZE as f32;
+        let max_freq = MAX_FREQ.min(sample_rate / 2f32);
+
+        let decay = DECAY_RATE * dt;
+        for band in 0..NUM_BANDS {
+            let low = MIN_FREQ * (max_freq / MIN_FREQ).powf(band as f32 / NUM_BANDS as f32);
+            let high = MIN_FREQ * (max_freq / MIN_FREQ).powf((band + 1) as f32 / NUM_BANDS as f32);
+            let start = ((low / bin_width) as usize).min(magnitudes.len() - 1);
+            let end = ((high / bin_width) as usize).clamp(start + 1, magnitudes.len());
+            let magnitude = magnitudes[start..end].iter().cloned().fold(0f32, f32::max);
+            let level = to_level(magnitude);
+            self.levels[band] = level.max(self.levels[band] - decay);
+        }
+
+        let rms = (self.window.iter().map(|v| v * v).sum::<f32>() / FFT_SIZE as f32).sqrt();
+        self.loudness = to_level(rms).max(self.loudness - decay);
+
+        let bass_bins = ((BASS_FREQ / bin_width) as usize).clamp(2, magnitudes.len());
+        let bass: f32 = magnitudes[1..bass_bins].iter().map(|m| m * m).sum();
+        let average = if self.bass_history.is_empty() {
+            0f32
+        } else {
+            self.bass_history.iter().sum::<f32>() / self.bass_history.len() as f32
+        };
+        let history_len = (BEAT_HISTORY / dt.max(f32::EPSILON)).ceil() as usize;
+        self.bass_history.push_back(bass);
+        while self.bass_history.len() > history_len.max(1) {
+            self.bass_history.pop_front();
+        }
+
+        self.since_beat += dt;
+        self.pulse *= (-dt / 0.25).exp();
+        if average > 0f32 && bass > average * BEAT_THRESHOLD && self.since_beat >= BEAT_COOLDOWN {
+            debug!(bass, average, "Beat detected");
+            self.since_beat = 0f32;
+            self.pulse = 1f32;
+            self.pulse_hue = (self.pulse_hue + 47f32) % 360f32;
+        }
+    }
+
+    fn render_spectrum(&self, leds: usize) -> Vec<BGRA8> {
+        (0..leds)
+            .map(|i| {
+                let band = i * NUM_BANDS / leds;
+                let hue = 300f32 * band as f32 / (NUM_BANDS - 1) as f32;
+                scale(&hue_color(hue), self.levels[band])
+            })
+            .collect()
+    }
+
+    fn render_vu_meter(&self, leds: usize) -> Vec<BGRA8> {
+        let lit = self.loudness * leds as f32;
+        (0..leds)
+            .map(|i| {
+                let brightness = (lit - i as f32).clamp(0f32, 1f32);
+                let hue = 120f32 * (1f32 - i as f32 / leds as f32);
+                scale(&hue_color(hue), brightness)
+            })
+            .collect()
+    }
+
+    fn render_pulse(&self, leds: usize) -> Vec<BGRA8> {
+        vec![scale(&hue_color(self.pulse_hue), self.pulse); leds]
+    }
+}
+
+impl Effect for AudioEffect {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let dt = time.saturating_sub(self.last_time).as_secs_f32();
+        self.last_time = time;
+
+        if !self.read_until(time) {
+            // fade out once the audio has finished
+            self.window.iter_mut().for_each(|value| *value = 0f32);
+        }
+        self.analyze(dt);
+
+        let leds = layout.len();
+        let pixels = match self.mode {
+            AudioMode::Spectrum => self.render_spectrum(leds),
+            AudioMode::VuMeter => self.render_vu_meter(leds),
+            AudioMode::Pulse => self.render_pulse(leds),
+        };
+        Sample::new(pixels, layout.width, layout.height)
+    }
+}
+
+/// Map an amplitude relative to full scale onto a level from 0 to 1 across the dynamic range.
+fn to_level(amplitude: f32) -> f32 {
+    let db = 20f32 * amplitude.max(f32::MIN_POSITIVE).log10();
+    ((db + DYNAMIC_RANGE_DB) / DYNAMIC_RANGE_DB).clamp(0f32, 1f32)
+}
+
+fn hue_color(hue: f32) -> BGRA8 {
+    let [r, g, b] = hsv_to_rgb([hue, 1f32, 1f32]);
+    let max = u8::MAX as f32;
+    rgb((r * max) as u8, (g * max) as u8, (b * max) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{WavSpec, WavWriter};
+    use std::io::Cursor;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// Mono 16-bit WAV data lasting `seconds`, with the value at each time given by `signal`.
+    fn wav(seconds: f32, signal: impl Fn(f32) -> f32) -> Box<dyn Read> {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut bytes = Vec::new();
+        let mut writer = WavWriter::new(Cursor::new(&mut bytes), spec).unwrap();
+        for i in 0..(seconds * SAMPLE_RATE as f32) as usize {
+            let value = signal(i as f32 / SAMPLE_RATE as f32);
+            writer
+                .write_sample((value * i16::MAX as f32) as i16)
+                .unwrap();
+        }
+        writer.finalize().unwrap();
+        Box::new(Cursor::new(bytes))
+    }
+
+    fn sine(frequency: f32, amplitude: f32) -> impl Fn(f32) -> f32 {
+        move |t| amplitude * (TAU * frequency * t).sin()
+    }
+
+    fn brightness(px: &BGRA8) -> u8 {
+        px.r.max(px.g).max(px.b)
+    }
+
+    fn lit(sample: &Sample) -> usize {
+        sample.pixels.iter().filter(|px| brightness(px) > 0).count()
+    }
+
+    #[test]
+    fn sine_lights_its_band() {
+        // 32 LEDs, so each of the 16 bands covers 2 LEDs, and 1kHz falls in band 8
+        let layout = Layout::new(10, 8);
+        let mut effect =
+            AudioEffect::new(wav(1f32, sine(1000f32, 0.5)), AudioMode::Spectrum).unwrap();
+        let sample = effect.render(&layout, Duration::from_millis(100));
+
+        let brightest = (0..sample.pixels.len())
+            .max_by_key(|i| brightness(&sample.pixels[*i]))
+            .unwrap();
+        assert_eq!(brightest / 2, 8);
+        for (i, px) in sample.pixels.iter().enumerate() {
+            if !(14..20).contains(&i) {
+                assert!(brightness(px) < 64, "LED {i} lit at {}", brightness(px));
+            }
+        }
+    }
+
+    #[test]
+    fn vu_meter_follows_amplitude() {
+        let layout = Layout::new(10, 8);
+        let time = Duration::from_millis(100);
+        let mut quiet =
+            AudioEffect::new(wav(1f32, sine(440f32, 0.01)), AudioMode::VuMeter).unwrap();
+        let mut loud = AudioEffect::new(wav(1f32, sine(440f32, 1f32)), AudioMode::VuMeter).unwrap();
+        let (quiet, loud) = (
+            lit(&quiet.render(&layout, time)),
+            lit(&loud.render(&layout, time)),
+        );
+        assert!(quiet > 0);
+        assert!(loud > quiet + 10, "quiet lit {quiet}, loud lit {loud}");
+    }
+
+    #[test]
+    fn bass_bursts_trigger_pulses() {
+        // a 0.1s burst of 60Hz every half second, after silence to detect the first one against
+        let bursts = wav(3f32, |t| {
+            if (0.25..0.35).contains(&(t % 0.5)) {
+                sine(60f32, 0.8)(t)
+            } else {
+                0f32
+            }
+        });
+        let layout = Layout::new(4, 3);
+        let mut effect = AudioEffect::new(bursts, AudioMode::Pulse).unwrap();
+
+        let mut pulses = 0;
+        let mut previous = 0u8;
+        for frame in 1..=180 {
+            let sample = effect.render(&layout, Duration::from_secs_f32(frame as f32 / 60f32));
+            let current = brightness(&sample.pixels[0]);
+            if current > previous.saturating_add(100) {
+                pulses += 1;
+            }
+            previous = current;
+        }
+        assert_eq!(pulses, 6);
+    }
+
+    #[test]
+    fn fades_out_once_input_ends() {
+        let layout = Layout::new(10, 8);
+        let mut effect =
+            AudioEffect::new(wav(1f32, sine(440f32, 1f32)), AudioMode::VuMeter).unwrap();
+        assert!(lit(&effect.render(&layout, Duration::from_millis(900))) > 0);
+
+        let mut sample = effect.render(&layout, Duration::from_millis(1100));
+        for ms in (1200..=2000).step_by(100) {
+            sample = effect.render(&layout, Duration::from_millis(ms));
+        }
+        assert_eq!(lit(&sample), 0);
+    }
+}
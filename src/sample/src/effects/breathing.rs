@@ -0,0 +1,32 @@
+use dxgcap::BGRA8;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use crate::color::scale;
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Effect that slowly fades every LED in and out with a single color.
+pub struct Breathing {
+    color: BGRA8,
+    period: Duration,
+}
+
+impl Breathing {
+    /// Create a new breathing effect that completes one fade in and out every `period`.
+    pub fn new(color: BGRA8, period: Duration) -> Self {
+        Self { color, period }
+    }
+}
+
+impl Effect for Breathing {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let phase = time.as_secs_f32() / self.period.as_secs_f32().max(f32::EPSILON);
+        let brightness = 0.5 - 0.5 * (phase * TAU).cos();
+        Sample::new(
+            vec![scale(&self.color, brightness); layout.len()],
+            layout.width,
+            layout.height,
+        )
+    }
+}
@@ -0,0 +1,60 @@
+use dxgcap::BGRA8;
+use std::time::Duration;
+
+use crate::color::scale;
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+use crate::rng::XorShift;
+
+/// Rate at which the candle picks a new brightness to flicker towards, in steps per second.
+const STEP_RATE: f32 = 30f32;
+
+/// Effect that lights every LED with a warm color, flickering irregularly like a candle flame.
+pub struct Candle {
+    color: BGRA8,
+    brightness: f32,
+    target: f32,
+    steps: u64,
+    rng: XorShift,
+}
+
+impl Candle {
+    pub fn new(color: BGRA8) -> Self {
+        Self {
+            color,
+            brightness: 1f32,
+            target: 1f32,
+            steps: 0,
+            rng: XorShift::from_time(),
+        }
+    }
+
+    /// Advance the flicker by one step.
+    fn step(&mut self) {
+        if self.rng.below(4) == 0 {
+            self.target = 0.55 + self.rng.next_f32() * 0.45;
+        }
+        self.brightness += (self.target - self.brightness) * 0.5;
+    }
+}
+
+impl Effect for Candle {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let target_steps = (time.as_secs_f32() * STEP_RATE) as u64;
+        let pending = target_steps
+            .saturating_sub(self.steps)
+            .min(STEP_RATE as u64);
+        for _ in 0..pending {
+            self.step();
+        }
+        self.steps = target_steps;
+
+        let pixels = (0..layout.len())
+            .map(|_| {
+                let variation = 0.95 + self.rng.next_f32() * 0.05;
+                scale(&self.color, self.brightness * variation)
+            })
+            .collect();
+        Sample::new(pixels, layout.width, layout.height)
+    }
+}
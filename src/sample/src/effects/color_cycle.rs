@@ -0,0 +1,33 @@
+use dxgcap::BGRA8;
+use std::time::Duration;
+
+use crate::color::{mix, rgb};
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Effect that lights every LED with the same color, fading through a list of colors in turn.
+pub struct ColorCycle {
+    colors: Vec<BGRA8>,
+    period: Duration,
+}
+
+impl ColorCycle {
+    /// Create a new color cycle that takes `period` to fade from each color to the next.
+    pub fn new(colors: Vec<BGRA8>, period: Duration) -> Self {
+        Self { colors, period }
+    }
+}
+
+impl Effect for ColorCycle {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let color = if self.colors.is_empty() {
+            rgb(0, 0, 0)
+        } else {
+            let position = time.as_secs_f32() / self.period.as_secs_f32().max(f32::EPSILON);
+            let from = position.floor() as usize % self.colors.len();
+            let to = (from + 1) % self.colors.len();
+            mix(&self.colors[from], &self.colors[to], position.fract())
+        };
+        Sample::new(vec![color; layout.len()], layout.width, layout.height)
+    }
+}
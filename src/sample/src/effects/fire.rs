@@ -0,0 +1,93 @@
+use dxgcap::BGRA8;
+use std::time::Duration;
+
+use crate::color::rgb;
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+use crate::rng::XorShift;
+
+/// Rate at which the fire simulation is advanced, in steps per second.
+const STEP_RATE: f32 = 60f32;
+
+/// Effect simulating flames rising from the middle of the bottom edge up both sides of the screen.
+pub struct Fire {
+    cooling: u8,
+    sparking: u8,
+    heat: Vec<u8>,
+    steps: u64,
+    rng: XorShift,
+}
+
+impl Fire {
+    /// Create a new fire.  Higher `cooling` gives shorter flames, and higher `sparking` gives a
+    /// more active fire.
+    pub fn new(cooling: u8, sparking: u8) -> Self {
+        Self {
+            cooling,
+            sparking,
+            heat: Vec::new(),
+            steps: 0,
+            rng: XorShift::from_time(),
+        }
+    }
+
+    /// Advance the simulation by one step.
+    fn step(&mut self) {
+        let cells = self.heat.len();
+        let max_cooling = (self.cooling as usize * 10 / cells + 2) as u8;
+
+        for heat in self.heat.iter_mut() {
+            *heat = heat.saturating_sub(self.rng.below(max_cooling as usize + 1) as u8);
+        }
+        for k in (2..cells).rev() {
+            self.heat[k] = ((self.heat[k - 1] as u16 + self.heat[k - 2] as u16 * 2) / 3) as u8;
+        }
+        if self.rng.below(u8::MAX as usize) < self.sparking as usize {
+            let y = self.rng.below(7.min(cells));
+            self.heat[y] = self.heat[y].saturating_add(160 + self.rng.below(96) as u8);
+        }
+    }
+
+    /// Map a heat value to a black, red, yellow, white color ramp.
+    fn heat_color(heat: u8) -> BGRA8 {
+        let t192 = (heat as u16 * 191 / 255) as u8;
+        let ramp = (t192 & 0x3f) << 2;
+        if t192 & 0x80 != 0 {
+            rgb(255, 255, ramp)
+        } else if t192 & 0x40 != 0 {
+            rgb(255, ramp, 0)
+        } else {
+            rgb(ramp, 0, 0)
+        }
+    }
+}
+
+impl Effect for Fire {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let leds = layout.len();
+        let cells = leds / 2 + 1;
+        if self.heat.len() != cells {
+            self.heat = vec![0; cells];
+        }
+
+        let target_steps = (time.as_secs_f32() * STEP_RATE) as u64;
+        let pending = target_steps
+            .saturating_sub(self.steps)
+            .min(STEP_RATE as u64);
+        for _ in 0..pending {
+            self.step();
+        }
+        self.steps = target_steps;
+
+        // the flames are mirrored around the middle of the bottom edge
+        let base = layout.corners()[2] + (layout.width - 1) / 2;
+        let pixels = (0..leds)
+            .map(|i| {
+                let offset = (i + leds - base) % leds;
+                let height = offset.min(leds - offset);
+                Fire::heat_color(self.heat[height])
+            })
+            .collect();
+        Sample::new(pixels, layout.width, layout.height)
+    }
+}
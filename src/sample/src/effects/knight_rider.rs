@@ -0,0 +1,48 @@
+use dxgcap::BGRA8;
+use std::time::Duration;
+
+use crate::color::scale;
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Effect that sweeps a single eye of light back and forth around the ring, trailing off on either
+/// side.
+pub struct KnightRider {
+    color: BGRA8,
+    width: usize,
+    period: Duration,
+}
+
+impl KnightRider {
+    /// Create a new scanner with an eye that fades out over `width` LEDs on each side, taking
+    /// `period` to sweep there and back.
+    pub fn new(color: BGRA8, width: usize, period: Duration) -> Self {
+        Self {
+            color,
+            width,
+            period,
+        }
+    }
+}
+
+impl Effect for KnightRider {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let leds = layout.len();
+        let phase = (time.as_secs_f32() / self.period.as_secs_f32().max(f32::EPSILON)).fract();
+        let sweep = if phase < 0.5 {
+            phase * 2f32
+        } else {
+            2f32 - phase * 2f32
+        };
+        let eye = sweep * (leds - 1) as f32;
+        let width = self.width.max(1) as f32;
+
+        let pixels = (0..leds)
+            .map(|i| {
+                let brightness = (1f32 - (i as f32 - eye).abs() / width).max(0f32);
+                scale(&self.color, brightness)
+            })
+            .collect();
+        Sample::new(pixels, layout.width, layout.height)
+    }
+}
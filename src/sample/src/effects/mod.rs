@@ -0,0 +1,50 @@
+pub mod audio;
+pub mod breathing;
+pub mod candle;
+pub mod color_cycle;
+pub mod fire;
+pub mod knight_rider;
+pub mod rainbow;
+pub mod script;
+pub mod solid;
+
+use crate::color::rgb;
+use crate::effect::Effect;
+use crate::effects::breathing::Breathing;
+use crate::effects::candle::Candle;
+use crate::effects::color_cycle::ColorCycle;
+use crate::effects::fire::Fire;
+use crate::effects::knight_rider::KnightRider;
+use crate::effects::rainbow::Rainbow;
+use crate::effects::solid::Solid;
+use std::time::Duration;
+
+/// Names of the built-in effects, as accepted by `builtin`.
+pub const BUILTIN_NAMES: [&str; 7] = [
+    "rainbow",
+    "breathing",
+    "color_cycle",
+    "fire",
+    "candle",
+    "solid",
+    "knight_rider",
+];
+
+/// Create a built-in effect by name with its default parameters.
+pub fn builtin(name: &str) -> Option<Box<dyn Effect>> {
+    let warm_white = rgb(255, 180, 100);
+    let effect: Box<dyn Effect> = match name {
+        "rainbow" => Box::new(Rainbow::new(Duration::from_secs(10), 1f32)),
+        "breathing" => Box::new(Breathing::new(warm_white, Duration::from_secs(6))),
+        "color_cycle" => Box::new(ColorCycle::new(
+            vec![rgb(255, 0, 0), rgb(0, 255, 0), rgb(0, 0, 255)],
+            Duration::from_secs(5),
+        )),
+        "fire" => Box::new(Fire::new(55, 120)),
+        "candle" => Box::new(Candle::new(rgb(255, 147, 41))),
+        "solid" => Box::new(Solid::new(warm_white)),
+        "knight_rider" => Box::new(KnightRider::new(rgb(255, 0, 0), 6, Duration::from_secs(2))),
+        _ => return None,
+    };
+    Some(effect)
+}
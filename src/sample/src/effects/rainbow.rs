@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use crate::color::{hsv_to_rgb, rgb};
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Effect that spreads the color spectrum around the ring and rotates it over time.
+pub struct Rainbow {
+    period: Duration,
+    repeats: f32,
+}
+
+impl Rainbow {
+    /// Create a new rainbow that completes a rotation every `period`, with the spectrum repeated
+    /// `repeats` times around the ring.
+    pub fn new(period: Duration, repeats: f32) -> Self {
+        Self { period, repeats }
+    }
+}
+
+impl Effect for Rainbow {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        let leds = layout.len();
+        let rotation = time.as_secs_f32() / self.period.as_secs_f32().max(f32::EPSILON);
+        let pixels = (0..leds)
+            .map(|i| {
+                let hue = 360f32 * (i as f32 / leds as f32 * self.repeats + rotation);
+                let [r, g, b] = hsv_to_rgb([hue, 1f32, 1f32]);
+                let max = u8::MAX as f32;
+                rgb((r * max) as u8, (g * max) as u8, (b * max) as u8)
+            })
+            .collect();
+        Sample::new(pixels, layout.width, layout.height)
+    }
+}
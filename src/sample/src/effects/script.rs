@@ -0,0 +1,291 @@
+use dxgcap::BGRA8;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{AST, Array, Dynamic, Engine, FLOAT, INT, Scope};
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, error, info, warn};
+
+use crate::color::rgb;
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Name of the function that scripts must define to render each frame.
+const RENDER_FN: &str = "render";
+
+/// How often the script file is checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Effect whose frames are rendered by a user-provided Rhai script.
+///
+/// The script must define `fn render(leds, time, screen)`, which is given the number of LEDs in
+/// the ring, the time in seconds since the effect started, and either the colors of the current
+/// screen sample or `()` if none has been provided.  It returns an array with a color for each LED,
+/// where each color is either an integer `0xRRGGBB` or an array `[r, g, b]`.  Colors in `screen`
+/// are given as `0xRRGGBB` integers.
+///
+/// Scripts have no access to the filesystem or other modules, and a frame that runs longer than
+/// the per-frame budget is aborted.  The script file is reloaded whenever it changes.
+pub struct ScriptEffect {
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+    modified: Option<SystemTime>,
+    last_checked: Instant,
+    deadline: Rc<Cell<Option<Instant>>>,
+    budget: Duration,
+    screen: Option<Sample>,
+    last_frame: Option<Vec<BGRA8>>,
+}
+
+impl ScriptEffect {
+    /// Load the script at `path`, allowing it to run for up to `budget` when rendering each frame.
+    pub fn new(path: &Path, budget: Duration) -> anyhow::Result<ScriptEffect> {
+        let deadline: Rc<Cell<Option<Instant>>> = Rc::new(Cell::new(None));
+        let engine = ScriptEffect::build_engine(deadline.clone());
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|err| anyhow::Error::msg(format!("Unable to load script: {err}")))?;
+
+        Ok(ScriptEffect {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+            modified: ScriptEffect::modified_time(path),
+            last_checked: Instant::now(),
+            deadline,
+            budget,
+            screen: None,
+            last_frame: None,
+        })
+    }
+
+    /// Create a script engine that is cut off from everything outside the script and that aborts
+    /// once `deadline` has passed.
+    fn build_engine(deadline: Rc<Cell<Option<Instant>>>) -> Engine {
+        let mut engine = Engine::new();
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(4096);
+        engine.set_max_array_size(4096);
+        engine.set_max_map_size(1024);
+        engine.on_print(|text| info!(script = text));
+        engine.on_debug(|text, _source, pos| debug!(script = text, ?pos));
+        engine.on_progress(move |_operations| match deadline.get() {
+            Some(deadline) if Instant::now() > deadline => Some(Dynamic::UNIT),
+            _ => None,
+        });
+        engine
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    /// Recompile the script if the file has changed since it was last loaded.  A script that fails
+    /// to compile is reported and the previous version is kept.
+    fn reload_if_changed(&mut self) {
+        if self.last_checked.elapsed() < RELOAD_INTERVAL {
+            return;
+        }
+        self.last_checked = Instant::now();
+
+        let modified = ScriptEffect::modified_time(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+
+        match self.engine.compile_file(self.path.clone()) {
+            Ok(ast) => {
+                info!(path = %self.path.display(), "Reloaded script");
+                self.ast = ast;
+            }
+            Err(err) => error!(path = %self.path.display(), "Unable to reload script: {}", err),
+        }
+    }
+
+    /// Convert a color returned by the script into a pixel.
+    fn to_pixel(value: &Dynamic) -> Option<BGRA8> {
+        if let Some(color) = value.clone().try_cast::<INT>() {
+            return Some(rgb((color >> 16) as u8, (color >> 8) as u8, color as u8));
+        }
+        let channels = value.clone().try_cast::<Array>()?;
+        let channel = |i: usize| {
+            channels
+                .get(i)
+                .and_then(|c| c.as_int().ok())
+                .map(|c| c.clamp(0, u8::MAX as INT) as u8)
+        };
+        Some(rgb(channel(0)?, channel(1)?, channel(2)?))
+    }
+}
+
+impl Effect for ScriptEffect {
+    fn render(&mut self, layout: &Layout, time: Duration) -> Sample {
+        self.reload_if_changed();
+
+        let leds = layout.len();
+        let screen = match &self.screen {
+            Some(sample) => Dynamic::from_array(
+                sample
+                    .pixels
+                    .iter()
+                    .map(|px| {
+                        Dynamic::from_int(
+                            ((px.r as INT) << 16) | ((px.g as INT) << 8) | px.b as INT,
+                        )
+                    })
+                    .collect(),
+            ),
+            None => Dynamic::UNIT,
+        };
+
+        self.deadline.set(Some(Instant::now() + self.budget));
+        let result = self.engine.call_fn::<Array>(
+            &mut Scope::new(),
+            &self.ast,
+            RENDER_FN,
+            (leds as INT, time.as_secs_f64() as FLOAT, screen),
+        );
+        self.deadline.set(None);
+
+        let pixels = match result {
+            Ok(colors) => {
+                let mut pixels: Vec<BGRA8> = colors
+                    .iter()
+                    .take(leds)
+                    .map(|color| ScriptEffect::to_pixel(color).unwrap_or(rgb(0, 0, 0)))
+                    .collect();
+                pixels.resize(leds, rgb(0, 0, 0));
+                self.last_frame = Some(pixels.clone());
+                pixels
+            }
+            Err(err) => {
+                warn!(path = %self.path.display(), "Script failed to render frame: {}", err);
+                match &self.last_frame {
+                    Some(pixels) if pixels.len() == leds => pixels.clone(),
+                    _ => vec![rgb(0, 0, 0); leds],
+                }
+            }
+        };
+
+        Sample::new(pixels, layout.width, layout.height)
+    }
+
+    /// Provide the most recent screen sample to pass to the script, or `None` to stop passing one.
+    fn set_screen(&mut self, screen: Option<Sample>) {
+        self.screen = screen;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Sampler;
+    use crate::dummy::DummySampler;
+    use crate::effect::EffectSampler;
+    use ls_screenshot::{FrameInfo, Screenshot};
+
+    /// Script file in the temp directory that is removed when dropped.
+    struct TempScript(PathBuf);
+
+    impl TempScript {
+        fn new(name: &str, source: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("ls-{}-{name}.rhai", std::process::id()));
+            fs::write(&path, source).unwrap();
+            TempScript(path)
+        }
+    }
+
+    impl Drop for TempScript {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    const ORANGE: &str = "fn render(leds, time, screen) { let out = []; for i in 0..leds { out.push(0xFF8000); } out }";
+
+    fn reds(sample: &Sample) -> Vec<u8> {
+        sample.pixels.iter().map(|px| px.r).collect()
+    }
+
+    #[test]
+    fn renders_script_colors() {
+        let script = TempScript::new("render", ORANGE);
+        let mut effect = ScriptEffect::new(&script.0, Duration::from_millis(100)).unwrap();
+        let sample = effect.render(&Layout::new(4, 3), Duration::ZERO);
+        assert_eq!(sample.pixels.len(), 10);
+        assert!(
+            sample
+                .pixels
+                .iter()
+                .all(|px| (px.r, px.g, px.b) == (255, 128, 0))
+        );
+    }
+
+    #[test]
+    fn runaway_frame_falls_back_to_last_frame() {
+        let script = TempScript::new(
+            "runaway",
+            "fn render(leds, time, screen) { if time > 0.5 { loop {} } let out = []; for i in 0..leds { out.push([i * 10, 0, 0]); } out }",
+        );
+        let layout = Layout::new(4, 3);
+        let mut effect = ScriptEffect::new(&script.0, Duration::from_millis(50)).unwrap();
+        let first = effect.render(&layout, Duration::ZERO);
+        assert_eq!(reds(&first), (0..10).map(|i| i * 10).collect::<Vec<u8>>());
+
+        let start = Instant::now();
+        let second = effect.render(&layout, Duration::from_secs(1));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(reds(&second), reds(&first));
+    }
+
+    #[test]
+    fn reloads_changed_script() {
+        let script = TempScript::new("reload", ORANGE);
+        let layout = Layout::new(4, 3);
+        let mut effect = ScriptEffect::new(&script.0, Duration::from_millis(100)).unwrap();
+        assert_eq!(effect.render(&layout, Duration::ZERO).pixels[0].r, 255);
+
+        fs::write(
+            &script.0,
+            "fn render(leds, time, screen) { let out = []; for i in 0..leds { out.push(0x102030); } out }",
+        )
+        .unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&script.0)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+
+        // not checked again until the reload interval has passed
+        assert_eq!(effect.render(&layout, Duration::ZERO).pixels[0].r, 255);
+        effect.last_checked -= RELOAD_INTERVAL;
+        let px = effect.render(&layout, Duration::ZERO).pixels[0];
+        assert_eq!((px.r, px.g, px.b), (0x10, 0x20, 0x30));
+    }
+
+    #[test]
+    fn effect_sampler_passes_screen_to_script() {
+        let script = TempScript::new("screen", "fn render(leds, time, screen) { screen }");
+        let effect = ScriptEffect::new(&script.0, Duration::from_millis(100)).unwrap();
+        let mut sampler = EffectSampler::new(4, 3, Box::new(effect))
+            .with_screen_sampler(Box::new(DummySampler::new(4, 3)));
+        let screenshot = Screenshot {
+            pixels: Vec::new(),
+            width: 0,
+            height: 0,
+            info: FrameInfo::new("test", 0),
+        };
+
+        let expected = DummySampler::new(4, 3).sample(&screenshot);
+        let sample = sampler.sample(&screenshot);
+        assert_eq!(reds(&sample), reds(&expected));
+    }
+}
@@ -0,0 +1,22 @@
+use dxgcap::BGRA8;
+use std::time::Duration;
+
+use crate::core::{Layout, Sample};
+use crate::effect::Effect;
+
+/// Effect that lights every LED with the same color.
+pub struct Solid {
+    color: BGRA8,
+}
+
+impl Solid {
+    pub fn new(color: BGRA8) -> Self {
+        Self { color }
+    }
+}
+
+impl Effect for Solid {
+    fn render(&mut self, layout: &Layout, _time: Duration) -> Sample {
+        Sample::new(vec![self.color; layout.len()], layout.width, layout.height)
+    }
+}
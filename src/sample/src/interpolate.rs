@@ -0,0 +1,140 @@
+use std::time::Duration;
+use time::OffsetDateTime;
+
+use crate::core::Sample;
+
+/// Produces frames for the outputs at a faster rate than samples are captured, by interpolating
+/// between the two most recent samples.  Output runs one capture interval behind the samples so
+/// that each frame can be blended towards a sample that has already arrived.
+pub struct Interpolator {
+    output_fps: f32,
+    previous: Option<Sample>,
+    latest: Option<Sample>,
+}
+
+impl Interpolator {
+    /// Create a new interpolator producing frames at `output_fps`.
+    pub fn new(output_fps: f32) -> Self {
+        Self {
+            output_fps,
+            previous: None,
+            latest: None,
+        }
+    }
+
+    /// Time between frames at the configured output rate.
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f32(1f32 / self.output_fps.max(f32::EPSILON))
+    }
+
+    /// Provide a newly captured sample.  Samples are spaced out by the capture time in their frame
+    /// information, rather than by when they arrive here.
+    pub fn push(&mut self, sample: Sample) {
+        self.previous = self.latest.replace(sample);
+    }
+
+    /// Get the frame to output at `now`, or `None` if no samples have been provided yet.
+    pub fn frame(&self, now: OffsetDateTime) -> Option<Sample> {
+        let latest = self.latest.as_ref()?;
+        let previous = match &self.previous {
+            Some(previous) if previous.pixels.len() == latest.pixels.len() => previous,
+            _ => return Some(latest.clone()),
+        };
+
+        let (previous_time, latest_time) = (previous.info.captured, latest.info.captured);
+        let capture_interval = (latest_time - previous_time).as_seconds_f32();
+        if capture_interval <= 0f32 {
+            return Some(latest.clone());
+        }
+        let t = ((now - latest_time).as_seconds_f32() / capture_interval).clamp(0f32, 1f32);
+
+        let colors: Vec<[f32; 3]> = previous
+            .colors()
+            .iter()
+            .zip(latest.colors().iter())
+            .map(|(from, to)| [0, 1, 2].map(|c| from[c] + (to[c] - from[c]) * t))
+            .collect();
+        let mut frame = latest.clone();
+        frame.set_colors(&colors);
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+    use ls_screenshot::FrameInfo;
+
+    fn captured(red: u8, leds: usize, frame_id: u64, captured: OffsetDateTime) -> Sample {
+        let info = FrameInfo {
+            captured,
+            ..FrameInfo::new("test", frame_id)
+        };
+        Sample::new(vec![rgb(red, 0, 0); leds], 4, 3).with_info(info)
+    }
+
+    fn red_at(interpolator: &Interpolator, now: OffsetDateTime) -> f32 {
+        interpolator.frame(now).expect("no frame").colors()[0][0]
+    }
+
+    /// Interpolator given a sample of red 100 and then one of red 200 captured 100ms apart, along
+    /// with the capture time of the second.
+    fn two_samples() -> (Interpolator, OffsetDateTime) {
+        let start = OffsetDateTime::now_utc() - Duration::from_secs(10);
+        let latest = start + Duration::from_millis(100);
+        let mut interpolator = Interpolator::new(120f32);
+        interpolator.push(captured(100, 10, 0, start));
+        interpolator.push(captured(200, 10, 1, latest));
+        (interpolator, latest)
+    }
+
+    #[test]
+    fn starts_at_previous_sample() {
+        let (interpolator, latest) = two_samples();
+        assert!((red_at(&interpolator, latest) - 100f32).abs() < 0.01);
+    }
+
+    #[test]
+    fn blends_by_capture_time() {
+        let (interpolator, latest) = two_samples();
+        let red = red_at(&interpolator, latest + Duration::from_millis(25));
+        assert!((red - 125f32).abs() < 0.01, "got {red}");
+        let red = red_at(&interpolator, latest + Duration::from_millis(50));
+        assert!((red - 150f32).abs() < 0.01, "got {red}");
+    }
+
+    #[test]
+    fn holds_latest_sample_once_reached() {
+        let (interpolator, latest) = two_samples();
+        assert!((red_at(&interpolator, latest + Duration::from_millis(100)) - 200f32).abs() < 0.01);
+        assert!((red_at(&interpolator, latest + Duration::from_secs(5)) - 200f32).abs() < 0.01);
+    }
+
+    #[test]
+    fn single_sample_is_output_as_is() {
+        let now = OffsetDateTime::now_utc();
+        let mut interpolator = Interpolator::new(120f32);
+        assert!(interpolator.frame(now).is_none());
+        interpolator.push(captured(100, 10, 0, now));
+        assert_eq!(
+            red_at(&interpolator, now + Duration::from_millis(50)),
+            100f32
+        );
+    }
+
+    #[test]
+    fn led_count_change_outputs_latest() {
+        let start = OffsetDateTime::now_utc();
+        let latest = start + Duration::from_millis(100);
+        let mut interpolator = Interpolator::new(120f32);
+        interpolator.push(captured(100, 10, 0, start));
+        interpolator.push(captured(200, 20, 1, latest));
+
+        let frame = interpolator
+            .frame(latest + Duration::from_millis(50))
+            .unwrap();
+        assert_eq!(frame.pixels.len(), 20);
+        assert_eq!(frame.pixels[0].r, 200);
+    }
+}
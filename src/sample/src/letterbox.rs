@@ -0,0 +1,157 @@
+use ls_screenshot::Screenshot;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Only every this many pixels across each row are checked when looking for black bars.
+const COLUMN_STRIDE: usize = 8;
+
+/// Settings for detecting black bars above and below the picture, such as when a widescreen movie
+/// is shown on a 16:9 screen.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LetterboxDetection {
+    /// Brightest channel value (0 to 255) that a pixel of a bar may have.
+    pub threshold: u8,
+    /// Largest fraction (0 to 0.5) of the screen height that each bar may cover.
+    pub max_fraction: f32,
+    /// Number of consecutive frames a new bar height must be detected for before it is used, so
+    /// that dark scenes don't make the bars appear to grow.
+    pub stable_frames: usize,
+}
+
+impl Default for LetterboxDetection {
+    fn default() -> Self {
+        Self {
+            threshold: 16,
+            max_fraction: 0.25,
+            stable_frames: 15,
+        }
+    }
+}
+
+/// Tracks the height of the black bars above and below the picture over time.
+pub(crate) struct LetterboxDetector {
+    config: LetterboxDetection,
+    /// Height of each bar currently in use, in pixels.
+    bar_px: usize,
+    /// Bar height most recently detected, and the number of consecutive frames it was detected for.
+    candidate_px: usize,
+    candidate_frames: usize,
+}
+
+impl LetterboxDetector {
+    pub(crate) fn new(config: LetterboxDetection) -> Self {
+        Self {
+            config,
+            bar_px: 0,
+            candidate_px: 0,
+            candidate_frames: 0,
+        }
+    }
+
+    /// Height of each bar currently in use, in pixels.
+    pub(crate) fn bar_px(&self) -> usize {
+        self.bar_px
+    }
+
+    /// Look for bars in a newly captured screenshot, returning whether the bar height in use
+    /// changed.
+    pub(crate) fn update(&mut self, screenshot: &Screenshot) -> bool {
+        let Some(detected) = self.detect(screenshot) else {
+            return false;
+        };
+
+        if detected == self.candidate_px {
+            self.candidate_frames += 1;
+        } else {
+            self.candidate_px = detected;
+            self.candidate_frames = 1;
+        }
+
+        if self.candidate_frames >= self.config.stable_frames && self.candidate_px != self.bar_px {
+            debug!(
+                from = self.bar_px,
+                to = self.candidate_px,
+                "Letterbox bar height changed"
+            );
+            self.bar_px = self.candidate_px;
+            return true;
+        }
+        false
+    }
+
+    /// Measure the height of the bars in a screenshot.  The smaller of the top and bottom bars is
+    /// used, since letterboxing is symmetric while dark content usually isn't.  Returns `None` if
+    /// no picture could be found within the largest bar height, such as on a black screen.
+    fn detect(&self, screenshot: &Screenshot) -> Option<usize> {
+        let max_px =
+            (screenshot.height as f32 * self.config.max_fraction.clamp(0f32, 0.5)) as usize;
+        let top = (0..max_px).find(|y| !self.is_dark_row(screenshot, *y))?;
+        let bottom =
+            (0..max_px).find(|y| !self.is_dark_row(screenshot, screenshot.height - 1 - y))?;
+        Some(top.min(bottom))
+    }
+
+    fn is_dark_row(&self, screenshot: &Screenshot, y: usize) -> bool {
+        let row = &screenshot.pixels[y * screenshot.width..(y + 1) * screenshot.width];
+        row.iter()
+            .step_by(COLUMN_STRIDE)
+            .all(|px| px.r.max(px.g).max(px.b) <= self.config.threshold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+    use ls_screenshot::FrameInfo;
+
+    fn screenshot(width: usize, height: usize, bar_px: usize) -> Screenshot {
+        let pixels = (0..width * height)
+            .map(|i| {
+                let y = i / width;
+                if y < bar_px || y >= height - bar_px {
+                    rgb(0, 0, 0)
+                } else {
+                    rgb(200, 100, 50)
+                }
+            })
+            .collect();
+        Screenshot {
+            pixels,
+            width,
+            height,
+            info: FrameInfo::new("test", 0),
+        }
+    }
+
+    fn detector() -> LetterboxDetector {
+        LetterboxDetector::new(LetterboxDetection {
+            stable_frames: 2,
+            ..LetterboxDetection::default()
+        })
+    }
+
+    #[test]
+    fn detects_bars_once_stable() {
+        let mut detector = detector();
+        let frame = screenshot(64, 40, 5);
+        assert!(!detector.update(&frame));
+        assert_eq!(detector.bar_px(), 0);
+        assert!(detector.update(&frame));
+        assert_eq!(detector.bar_px(), 5);
+    }
+
+    #[test]
+    fn black_screen_keeps_bars() {
+        let mut detector = detector();
+        let frame = screenshot(64, 40, 5);
+        detector.update(&frame);
+        detector.update(&frame);
+
+        let black = screenshot(64, 40, 20);
+        assert!(!detector.update(&black));
+        assert!(!detector.update(&black));
+        assert_eq!(detector.bar_px(), 5);
+    }
+}
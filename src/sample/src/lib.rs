@@ -1,3 +1,19 @@
+pub mod average;
 pub mod avg_rec;
+pub mod color;
 pub mod core;
 pub mod dummy;
+pub mod effect;
+pub mod effects;
+pub mod interpolate;
+pub mod letterbox;
+pub mod mask;
+pub mod mixer;
+pub mod pattern;
+pub mod preset;
+pub mod rect;
+mod region;
+mod rng;
+pub mod stages;
+pub mod transition;
+pub mod zone;
@@ -0,0 +1,177 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::rect::NormRect;
+
+/// Areas of the screen, such as taskbars, HUDs, subtitles, and channel logos, that samplers skip
+/// when computing the colors of their regions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExclusionMask {
+    pub rects: Vec<NormRect>,
+    pub image: Option<MaskImage>,
+}
+
+impl ExclusionMask {
+    pub fn new(rects: Vec<NormRect>, image: Option<MaskImage>) -> Self {
+        Self { rects, image }
+    }
+
+    /// Whether the normalized point `(x, y)` is excluded from sampling.
+    pub fn excludes(&self, x: f32, y: f32) -> bool {
+        self.rects.iter().any(|rect| rect.contains(x, y))
+            || self
+                .image
+                .as_ref()
+                .is_some_and(|image| image.excludes(x, y))
+    }
+
+    /// Resolve the mask against a screen of the given size, producing a flag for each pixel that
+    /// is set when the pixel is excluded.
+    pub(crate) fn rasterize(&self, img_width_px: usize, img_height_px: usize) -> Vec<bool> {
+        let mut excluded = vec![false; img_width_px * img_height_px];
+        if let Some(image) = &self.image {
+            for (i, flag) in excluded.iter_mut().enumerate() {
+                let x = ((i % img_width_px) as f32 + 0.5) / img_width_px as f32;
+                let y = ((i / img_width_px) as f32 + 0.5) / img_height_px as f32;
+                *flag = image.excludes(x, y);
+            }
+        }
+        for rect in &self.rects {
+            let [start_x, end_x, start_y, end_y] = rect.to_pixels(img_width_px, img_height_px);
+            for y in start_y..end_y {
+                excluded[y * img_width_px + start_x..y * img_width_px + end_x].fill(true);
+            }
+        }
+        excluded
+    }
+}
+
+/// Bitmap marking excluded areas of the screen, stretched to cover the whole screen regardless of
+/// its resolution.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "MaskImageConfig")]
+pub struct MaskImage {
+    width: usize,
+    height: usize,
+    excluded: Vec<bool>,
+}
+
+/// Mask image as read from config, before it has been checked.
+#[derive(Deserialize)]
+struct MaskImageConfig {
+    width: usize,
+    height: usize,
+    excluded: Vec<bool>,
+}
+
+impl TryFrom<MaskImageConfig> for MaskImage {
+    type Error = anyhow::Error;
+
+    fn try_from(config: MaskImageConfig) -> anyhow::Result<Self> {
+        MaskImage::new(config.width, config.height, config.excluded)
+    }
+}
+
+impl MaskImage {
+    /// Create a mask image from one flag per pixel, in row-major order, that is set for each
+    /// excluded pixel.
+    pub fn new(width: usize, height: usize, excluded: Vec<bool>) -> anyhow::Result<Self> {
+        if width == 0 || height == 0 {
+            bail!("Mask image must not be empty, got {width}x{height}");
+        }
+        if excluded.len() != width * height {
+            bail!(
+                "Mask image must have one flag per pixel, got {} flags for {width}x{height}",
+                excluded.len()
+            );
+        }
+        Ok(Self {
+            width,
+            height,
+            excluded,
+        })
+    }
+
+    /// Create a mask image from grayscale values, in row-major order, where pixels darker than
+    /// half intensity are excluded.
+    pub fn from_luma(width: usize, height: usize, values: &[u8]) -> anyhow::Result<Self> {
+        Self::new(
+            width,
+            height,
+            values.iter().map(|value| *value < 128).collect(),
+        )
+    }
+
+    /// Whether the normalized point `(x, y)` falls on an excluded pixel of the image.
+    fn excludes(&self, x: f32, y: f32) -> bool {
+        if !(0f32..1f32).contains(&x) || !(0f32..1f32).contains(&y) {
+            return false;
+        }
+        let px = (x * self.width as f32) as usize;
+        let py = (y * self.height as f32) as usize;
+        self.excluded
+            .get(py * self.width + px)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Exclusion masks for each sampling profile, keyed by profile name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExclusionMasks {
+    profiles: HashMap<String, ExclusionMask>,
+}
+
+impl ExclusionMasks {
+    pub fn new() -> Self {
+        Self {
+            profiles: HashMap::new(),
+        }
+    }
+
+    /// Get the mask for a profile, if any.
+    pub fn get(&self, profile: &str) -> Option<&ExclusionMask> {
+        self.profiles.get(profile)
+    }
+
+    /// Set the mask for a profile, returning the mask previously set for it.
+    pub fn insert(&mut self, profile: &str, mask: ExclusionMask) -> Option<ExclusionMask> {
+        self.profiles.insert(profile.to_string(), mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_rejects_wrong_flag_count() {
+        assert!(MaskImage::new(2, 2, vec![false; 3]).is_err());
+        assert!(MaskImage::from_luma(2, 2, &[0; 5]).is_err());
+        assert!(MaskImage::new(2, 2, vec![false; 4]).is_ok());
+    }
+
+    #[test]
+    fn image_rejects_empty_size() {
+        assert!(MaskImage::new(0, 2, Vec::new()).is_err());
+        assert!(MaskImage::new(2, 0, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn rasterizes_negative_size_rect() {
+        let mask = ExclusionMask::new(vec![NormRect::new(1f32, 1f32, -0.5, -0.5)], None);
+        let excluded = mask.rasterize(4, 4);
+        let expected: Vec<bool> = (0..16).map(|i| i % 4 >= 2 && i / 4 >= 2).collect();
+        assert_eq!(excluded, expected);
+    }
+
+    #[test]
+    fn rasterizes_stretched_image() {
+        let image = MaskImage::new(2, 1, vec![true, false]).unwrap();
+        let excluded = ExclusionMask::new(Vec::new(), Some(image)).rasterize(4, 2);
+        let expected: Vec<bool> = (0..8).map(|i| i % 4 < 2).collect();
+        assert_eq!(excluded, expected);
+    }
+}
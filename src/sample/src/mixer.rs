@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::debug;
+
+use crate::core::Sample;
+use crate::transition::{Easing, Transition};
+
+/// A source of samples feeding into a `Mixer`.
+struct Source {
+    priority: i32,
+    sample: Sample,
+    expires: Option<OffsetDateTime>,
+}
+
+/// In-progress blend from the previously active source to the newly active one.
+struct Blend {
+    /// Source being blended away from, while it is still available.
+    from_source: Option<String>,
+    /// Last frame shown before the switch, used once the previous source is no longer available.
+    from_frame: Sample,
+    transition: Transition,
+}
+
+/// Combines several sources of samples that are active at once, such as screen sampling, an effect,
+/// and short-lived notifications pushed by other programs, into the single stream sent to the
+/// outputs.  The highest priority source that hasn't timed out is shown, blending over from the
+/// previous source whenever the active source changes.
+pub struct Mixer {
+    sources: HashMap<String, Source>,
+    blend_duration: Duration,
+    easing: Easing,
+    active: Option<String>,
+    blend: Option<Blend>,
+    last_output: Option<Sample>,
+}
+
+impl Mixer {
+    /// Create a new mixer that takes `blend_duration` to blend between sources, following the
+    /// `easing` curve.
+    pub fn new(blend_duration: Duration, easing: Easing) -> Self {
+        Self {
+            sources: HashMap::new(),
+            blend_duration,
+            easing,
+            active: None,
+            blend: None,
+            last_output: None,
+        }
+    }
+
+    /// Provide the latest sample from the source called `name`, adding the source if it is new.  If
+    /// `timeout` is given, the source is removed once that long passes without a new sample.
+    pub fn push(&mut self, name: &str, priority: i32, sample: Sample, timeout: Option<Duration>) {
+        let now = OffsetDateTime::now_utc();
+        self.sources.insert(
+            name.to_string(),
+            Source {
+                priority,
+                sample,
+                expires: timeout.map(|timeout| now + timeout),
+            },
+        );
+    }
+
+    /// Remove the source called `name`.
+    pub fn remove(&mut self, name: &str) {
+        self.sources.remove(name);
+    }
+
+    /// Get the frame to send to the outputs now, or `None` if there are no sources.
+    pub fn output(&mut self) -> Option<Sample> {
+        let now = OffsetDateTime::now_utc();
+        self.sources
+            .retain(|_, source| source.expires.is_none_or(|expires| now < expires));
+
+        let active = self
+            .sources
+            .iter()
+            .max_by(|(a_name, a), (b_name, b)| {
+                // break ties by name so that the active source doesn't flip between frames
+                a.priority.cmp(&b.priority).then_with(|| b_name.cmp(a_name))
+            })
+            .map(|(name, _)| name.clone());
+        if active != self.active {
+            debug!(from = ?self.active, to = ?active, "Switching active source");
+            // a switch part way through a blend starts from the partially blended frame
+            let from_source = match self.blend {
+                Some(_) => None,
+                None => self.active.take(),
+            };
+            // the last frame shown is kept even once every source is gone, so that a source
+            // appearing afterwards still blends in from it
+            self.blend = self.last_output.clone().map(|from_frame| Blend {
+                from_source,
+                from_frame,
+                transition: Transition::new(self.blend_duration, self.easing),
+            });
+            self.active = active;
+        }
+
+        let target = &self.sources.get(self.active.as_ref()?)?.sample;
+        let output = match &self.blend {
+            Some(blend) if !blend.transition.is_finished() => {
+                let from = blend
+                    .from_source
+                    .as_ref()
+                    .and_then(|name| self.sources.get(name))
+                    .map_or(&blend.from_frame, |source| &source.sample);
+                blend.transition.blend(from, target)
+            }
+            _ => {
+                self.blend = None;
+                target.clone()
+            }
+        };
+
+        self.last_output = Some(output.clone());
+        Some(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+    use std::thread;
+
+    fn solid(r: u8, g: u8, b: u8) -> Sample {
+        Sample::new(vec![rgb(r, g, b); 10], 4, 3)
+    }
+
+    fn assert_output(mixer: &mut Mixer, expected: (u8, u8, u8)) {
+        let px = mixer.output().expect("no output").pixels[0];
+        assert_eq!((px.r, px.g, px.b), expected);
+    }
+
+    fn instant() -> Mixer {
+        Mixer::new(Duration::ZERO, Easing::Linear)
+    }
+
+    /// Mixer whose blends take so long that they stay at the frame being blended from.
+    fn frozen() -> Mixer {
+        Mixer::new(Duration::from_secs(1000), Easing::Linear)
+    }
+
+    #[test]
+    fn highest_priority_is_shown() {
+        let mut mixer = instant();
+        assert!(mixer.output().is_none());
+        mixer.push("screen", 0, solid(255, 0, 0), None);
+        mixer.push("notification", 10, solid(0, 255, 0), None);
+        mixer.push("effect", 5, solid(0, 0, 255), None);
+        assert_output(&mut mixer, (0, 255, 0));
+    }
+
+    #[test]
+    fn same_priority_ties_are_stable() {
+        let mut mixer = instant();
+        mixer.push("b", 0, solid(255, 0, 0), None);
+        mixer.push("a", 0, solid(0, 255, 0), None);
+        for _ in 0..5 {
+            assert_output(&mut mixer, (0, 255, 0));
+        }
+    }
+
+    #[test]
+    fn expired_source_falls_back_to_lower_priority() {
+        let mut mixer = instant();
+        mixer.push("screen", 0, solid(255, 0, 0), None);
+        mixer.push(
+            "notification",
+            10,
+            solid(0, 255, 0),
+            Some(Duration::from_millis(20)),
+        );
+        assert_output(&mut mixer, (0, 255, 0));
+
+        thread::sleep(Duration::from_millis(30));
+        assert_output(&mut mixer, (255, 0, 0));
+    }
+
+    #[test]
+    fn switch_during_blend_starts_from_blended_frame() {
+        let mut mixer = frozen();
+        mixer.push("screen", 0, solid(255, 0, 0), None);
+        assert_output(&mut mixer, (255, 0, 0));
+
+        mixer.push("effect", 5, solid(0, 0, 255), None);
+        assert_output(&mut mixer, (255, 0, 0));
+
+        // the new blend starts from what was shown, rather than from the effect it interrupted,
+        // and is unaffected by the sources it came from going away
+        mixer.push("notification", 10, solid(0, 255, 0), None);
+        assert_output(&mut mixer, (255, 0, 0));
+        mixer.remove("screen");
+        mixer.remove("effect");
+        assert_output(&mut mixer, (255, 0, 0));
+    }
+
+    #[test]
+    fn source_after_all_expire_blends_from_last_frame() {
+        let mut mixer = frozen();
+        mixer.push(
+            "notification",
+            10,
+            solid(255, 0, 0),
+            Some(Duration::from_millis(20)),
+        );
+        assert_output(&mut mixer, (255, 0, 0));
+
+        thread::sleep(Duration::from_millis(30));
+        assert!(mixer.output().is_none());
+
+        mixer.push("screen", 0, solid(0, 0, 255), None);
+        assert_output(&mut mixer, (255, 0, 0));
+    }
+}
@@ -0,0 +1,133 @@
+use dxgcap::BGRA8;
+use ls_screenshot::{FrameInfo, Screenshot};
+
+use crate::color::rgb;
+use crate::core::{Layout, Sample, Sampler};
+
+const OFF: BGRA8 = rgb(0, 0, 0);
+const WHITE: BGRA8 = rgb(u8::MAX, u8::MAX, u8::MAX);
+const RED: BGRA8 = rgb(u8::MAX, 0, 0);
+const GREEN: BGRA8 = rgb(0, u8::MAX, 0);
+const BLUE: BGRA8 = rgb(0, 0, u8::MAX);
+const YELLOW: BGRA8 = rgb(u8::MAX, u8::MAX, 0);
+
+/// Number of LEDs in each repeat of the `Direction` pattern.
+const ARROW_LENGTH: usize = 5;
+
+/// Identification pattern used to verify the wiring order and layout of a strip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// A single white LED stepping one position around the ring each frame.
+    Chase,
+    /// Each edge lit a different color, with top red, right green, bottom blue, and left yellow.
+    /// The corners are lit white.
+    EdgeColors,
+    /// The index of each LED encoded in binary across frames: a red sync frame, followed by one
+    /// frame per bit (least significant first) showing green for a set bit and blue for a clear
+    /// one.
+    BinaryIndex,
+    /// The first LED lit white and the last LED lit red, with all others off.
+    FirstLed,
+    /// Repeating ramps of brightness, brightest at the end with the higher index, scrolling one LED
+    /// per frame in the direction of increasing index.
+    Direction,
+}
+
+impl Pattern {
+    /// Number of frames before the pattern repeats, for a ring of `leds` LEDs.
+    pub fn period(&self, leds: usize) -> usize {
+        match self {
+            Pattern::Chase => leds,
+            Pattern::EdgeColors | Pattern::FirstLed => 1,
+            Pattern::BinaryIndex => Self::index_bits(leds) + 1,
+            Pattern::Direction => ARROW_LENGTH,
+        }
+    }
+
+    /// Render frame number `frame` of the pattern.
+    pub fn render(&self, layout: &Layout, frame: usize) -> Vec<BGRA8> {
+        let leds = layout.len();
+        let step = frame % self.period(leds).max(1);
+        let corners = layout.corners();
+
+        (0..leds)
+            .map(|i| match self {
+                Pattern::Chase => {
+                    if i == step {
+                        WHITE
+                    } else {
+                        OFF
+                    }
+                }
+                Pattern::EdgeColors => {
+                    if corners.contains(&i) {
+                        WHITE
+                    } else {
+                        match (0..4).rev().find(|e| corners[*e] <= i).unwrap() {
+                            0 => RED,
+                            1 => GREEN,
+                            2 => BLUE,
+                            _ => YELLOW,
+                        }
+                    }
+                }
+                Pattern::BinaryIndex => match step {
+                    0 => RED,
+                    bit if (i >> (bit - 1)) & 1 == 1 => GREEN,
+                    _ => BLUE,
+                },
+                Pattern::FirstLed => match i {
+                    0 => WHITE,
+                    i if i == leds - 1 => RED,
+                    _ => OFF,
+                },
+                Pattern::Direction => {
+                    let position = (i + ARROW_LENGTH - step) % ARROW_LENGTH;
+                    let value = (u8::MAX as usize * (position + 1) / ARROW_LENGTH) as u8;
+                    rgb(value, value, value)
+                }
+            })
+            .collect()
+    }
+
+    /// Number of bits needed to represent the index of every LED in a ring of `leds` LEDs.
+    fn index_bits(leds: usize) -> usize {
+        (usize::BITS - leds.saturating_sub(1).leading_zeros()).max(1) as usize
+    }
+}
+
+/// Sampler that ignores the screen and outputs a `Pattern`, advancing one frame each time it is
+/// sampled, so that the pattern can be sent to a strip through the normal output path.
+pub struct PatternSampler {
+    layout: Layout,
+    pattern: Pattern,
+    frame: usize,
+    frame_id: u64,
+}
+
+impl PatternSampler {
+    pub fn new(width: usize, height: usize, pattern: Pattern) -> Self {
+        Self {
+            layout: Layout::new(width, height),
+            pattern,
+            frame: 0,
+            frame_id: 0,
+        }
+    }
+
+    /// Switch to a different pattern, starting from its first frame.
+    pub fn set_pattern(&mut self, pattern: Pattern) {
+        self.pattern = pattern;
+        self.frame = 0;
+    }
+}
+
+impl Sampler for PatternSampler {
+    fn sample(&mut self, _screenshot: &Screenshot) -> Sample {
+        let pixels = self.pattern.render(&self.layout, self.frame);
+        self.frame = (self.frame + 1) % self.pattern.period(self.layout.len()).max(1);
+        let info = FrameInfo::new("pattern", self.frame_id);
+        self.frame_id += 1;
+        Sample::new(pixels, self.layout.width, self.layout.height).with_info(info)
+    }
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::average::{AverageOptions, AveragingMode};
+use crate::avg_rec::AvgRectangleSampler;
+use crate::letterbox::LetterboxDetection;
+use crate::stages::Pipeline;
+use crate::stages::adjust::{AdjustStage, Adjustment};
+use crate::stages::smooth::{SmoothingMode, SmoothingStage};
+
+/// Sampling and processing settings tuned for a kind of content.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    /// How the pixels of each sampled region are averaged together.
+    pub averaging: AverageOptions,
+    /// Only every `stride`th pixel across and down each region is sampled.
+    pub stride: usize,
+    /// Detection of black bars above and below the picture, or `None` to always sample the edges
+    /// of the screen.
+    pub letterbox: Option<LetterboxDetection>,
+    /// Smoothing applied to each LED over time, or `None` to show each sample as soon as it is
+    /// taken.
+    pub smoothing: Option<SmoothingMode>,
+    /// Frame difference (0 to 1) above which smoothing is bypassed, see `SmoothingStage::new`.
+    pub scene_cut_threshold: f32,
+    pub adjustment: Adjustment,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            averaging: AverageOptions::default(),
+            stride: 1,
+            letterbox: None,
+            smoothing: None,
+            scene_cut_threshold: 1f32,
+            adjustment: Adjustment::default(),
+        }
+    }
+}
+
+impl Preset {
+    /// Heavy smoothing and accurate, saturated colors, sampling the edge of the picture rather than
+    /// any letterbox bars around it.
+    pub fn movie() -> Self {
+        Self {
+            averaging: AverageOptions {
+                mode: AveragingMode::Linear,
+                ..AverageOptions::default()
+            },
+            stride: 2,
+            letterbox: Some(LetterboxDetection::default()),
+            smoothing: Some(SmoothingMode::Settle {
+                settle_time: Duration::from_millis(500),
+            }),
+            scene_cut_threshold: 0.25,
+            adjustment: Adjustment {
+                saturation_gain: 1.2,
+                ..Adjustment::default()
+            },
+        }
+    }
+
+    /// Minimal latency, showing every sample as soon as it is taken.
+    pub fn game() -> Self {
+        Self {
+            averaging: AverageOptions::default(),
+            stride: 4,
+            letterbox: None,
+            smoothing: None,
+            scene_cut_threshold: 1f32,
+            adjustment: Adjustment {
+                saturation_gain: 1.1,
+                ..Adjustment::default()
+            },
+        }
+    }
+
+    /// Dim, desaturated light that doesn't distract from mostly static, bright content.
+    pub fn desktop() -> Self {
+        Self {
+            averaging: AverageOptions::default(),
+            stride: 4,
+            letterbox: None,
+            smoothing: Some(SmoothingMode::Exponential { alpha: 0.1 }),
+            scene_cut_threshold: 1f32,
+            adjustment: Adjustment {
+                saturation_gain: 0.6,
+                luminance_gain: 0.5,
+                ..Adjustment::default()
+            },
+        }
+    }
+
+    /// Apply the preset's sampling settings to a sampler.
+    pub fn configure(&self, sampler: &mut AvgRectangleSampler) {
+        sampler.set_averaging(self.averaging);
+        sampler.set_stride(self.stride);
+        sampler.set_letterbox_detection(self.letterbox);
+    }
+
+    /// Build the processing stages for the preset.
+    pub fn pipeline(&self) -> Pipeline {
+        let mut pipeline = Pipeline::new();
+        pipeline.push(Box::new(AdjustStage::new(self.adjustment.clone())));
+        if let Some(mode) = self.smoothing {
+            pipeline.push(Box::new(SmoothingStage::new(
+                mode,
+                self.scene_cut_threshold,
+            )));
+        }
+        pipeline
+    }
+}
+
+/// Named presets that can be switched between at runtime.  The built-in `movie`, `game`, and
+/// `desktop` presets are always available, and presets loaded from config are added to them,
+/// replacing any built-in preset of the same name.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "HashMap<String, Preset>", into = "HashMap<String, Preset>")]
+pub struct Presets {
+    presets: HashMap<String, Preset>,
+}
+
+impl Default for Presets {
+    fn default() -> Self {
+        let mut presets = HashMap::new();
+        presets.insert("movie".to_string(), Preset::movie());
+        presets.insert("game".to_string(), Preset::game());
+        presets.insert("desktop".to_string(), Preset::desktop());
+        Self { presets }
+    }
+}
+
+impl From<HashMap<String, Preset>> for Presets {
+    fn from(configured: HashMap<String, Preset>) -> Self {
+        let mut presets = Presets::default();
+        presets.presets.extend(configured);
+        presets
+    }
+}
+
+impl From<Presets> for HashMap<String, Preset> {
+    fn from(presets: Presets) -> Self {
+        presets.presets
+    }
+}
+
+impl Presets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the preset with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.get(name)
+    }
+
+    /// Add a preset, returning the preset it replaced, if any.
+    pub fn insert(&mut self, name: &str, preset: Preset) -> Option<Preset> {
+        self.presets.insert(name.to_string(), preset)
+    }
+
+    /// Names of all available presets, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.presets.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
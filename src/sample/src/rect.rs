@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// Rectangle specified in coordinates normalized to the dimensions of the screen, where `(0, 0)` is
+/// the top-left corner of the screen and `(1, 1)` is the bottom-right corner.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct NormRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl NormRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Get the left, right, top, and bottom edges of the rectangle, in that order.  A negative
+    /// width or height extends the rectangle left or up from `(x, y)`.
+    fn edges(&self) -> [f32; 4] {
+        [
+            self.x.min(self.x + self.width),
+            self.x.max(self.x + self.width),
+            self.y.min(self.y + self.height),
+            self.y.max(self.y + self.height),
+        ]
+    }
+
+    /// Whether the normalized point `(x, y)` lies within this rectangle.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let [left, right, top, bottom] = self.edges();
+        left <= x && x < right && top <= y && y < bottom
+    }
+
+    /// Get the pixel bounds `(start_x, end_x, start_y, end_y)` covered by this rectangle on an
+    /// image of the given size, clamped to the image.
+    pub(crate) fn to_pixels(self, img_width_px: usize, img_height_px: usize) -> [usize; 4] {
+        let scale =
+            |value: f32, max: usize| ((value * max as f32).round().max(0f32) as usize).min(max);
+        let [left, right, top, bottom] = self.edges();
+        [
+            scale(left, img_width_px),
+            scale(right, img_width_px),
+            scale(top, img_height_px),
+            scale(bottom, img_height_px),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_size_extends_left_and_up() {
+        let rect = NormRect::new(0.75, 0.5, -0.5, -0.25);
+        assert_eq!(rect.to_pixels(100, 100), [25, 75, 25, 50]);
+        assert!(rect.contains(0.5, 0.4));
+        assert!(!rect.contains(0.8, 0.4));
+        assert!(!rect.contains(0.5, 0.6));
+    }
+
+    #[test]
+    fn pixels_are_clamped_to_image() {
+        let rect = NormRect::new(-0.5, 0.5, 2f32, 1f32);
+        assert_eq!(rect.to_pixels(100, 50), [0, 100, 25, 50]);
+    }
+}
@@ -0,0 +1,131 @@
+use dxgcap::BGRA8;
+use ls_screenshot::Screenshot;
+
+use crate::average::{AverageOptions, average};
+use crate::mask::ExclusionMask;
+use crate::rng::XorShift;
+
+#[derive(Debug)]
+pub(crate) struct Region {
+    start_x: usize,
+    end_x: usize,
+    start_y: usize,
+    end_y: usize,
+}
+
+impl Region {
+    pub(crate) fn new(start_x: usize, end_x: usize, start_y: usize, end_y: usize) -> Region {
+        Region {
+            start_x,
+            end_x,
+            start_y,
+            end_y,
+        }
+    }
+
+    /// Iterate over every `stride`th pixel of the region in each direction, starting `offset_x`
+    /// and `offset_y` pixels in from the top-left corner.  Offsets larger than the region wrap
+    /// around so that small regions are never skipped entirely.
+    pub(crate) fn iter_strided(
+        &self,
+        stride: usize,
+        offset_x: usize,
+        offset_y: usize,
+    ) -> RegionIter<'_> {
+        let x = self.start_x + offset_x % (self.end_x - self.start_x).max(1);
+        let y = self.start_y + offset_y % (self.end_y - self.start_y).max(1);
+        RegionIter {
+            region: self,
+            stride,
+            start_x: x,
+            x,
+            y: if x < self.end_x { y } else { self.end_y },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RegionIter<'a> {
+    region: &'a Region,
+    stride: usize,
+    start_x: usize,
+    x: usize,
+    y: usize,
+}
+
+impl Iterator for RegionIter<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.region.end_y {
+            return None;
+        }
+
+        let out = (self.x, self.y);
+        self.x += self.stride;
+        if self.x >= self.region.end_x {
+            self.x = self.start_x;
+            self.y += self.stride;
+        }
+        Some(out)
+    }
+}
+
+/// Averages the pixels of a set of regions of a screenshot, shared by the samplers so that they
+/// all apply the averaging options, stride, and exclusion mask in the same way.
+pub(crate) struct RegionAverager {
+    pub(crate) options: AverageOptions,
+    stride: usize,
+    rng: XorShift,
+    mask: Option<ExclusionMask>,
+    excluded: Option<Vec<bool>>,
+}
+
+impl RegionAverager {
+    pub(crate) fn new() -> Self {
+        Self {
+            options: AverageOptions::default(),
+            stride: 1,
+            rng: XorShift::from_time(),
+            mask: None,
+            excluded: None,
+        }
+    }
+
+    /// Only sample every `stride`th pixel across and down each region.  The sampled pixels are
+    /// shifted randomly each frame to avoid aliasing against regular patterns on screen.
+    pub(crate) fn set_stride(&mut self, stride: usize) {
+        self.stride = stride.max(1);
+    }
+
+    /// Replace the exclusion mask applied to the screen, or remove it if `None`.
+    pub(crate) fn set_mask(&mut self, mask: Option<ExclusionMask>) {
+        self.mask = mask;
+        self.excluded = None;
+    }
+
+    /// Compute the average color of each region of a screenshot, skipping excluded pixels.
+    pub(crate) fn average(&mut self, screenshot: &Screenshot, regions: &[Region]) -> Vec<BGRA8> {
+        if self.excluded.is_none()
+            && let Some(mask) = &self.mask
+        {
+            self.excluded = Some(mask.rasterize(screenshot.width, screenshot.height));
+        }
+
+        let (offset_x, offset_y) = (self.rng.below(self.stride), self.rng.below(self.stride));
+        let excluded = self.excluded.as_deref();
+        regions
+            .iter()
+            .map(|region| {
+                average(
+                    region
+                        .iter_strided(self.stride, offset_x, offset_y)
+                        .map(|(x, y)| y * screenshot.width + x)
+                        .filter(|i| !excluded.is_some_and(|excluded| excluded[*i]))
+                        .map(|i| screenshot.pixels[i]),
+                    &self.options,
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,35 @@
+use time::OffsetDateTime;
+
+/// Small, fast pseudo-random number generator for visual jitter and effects, where statistical
+/// quality doesn't matter.
+pub(crate) struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    /// Create a generator seeded from the current time.
+    pub(crate) fn from_time() -> Self {
+        Self::new(OffsetDateTime::now_utc().unix_timestamp_nanos() as u64)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Get a value in the range `0..n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Get a value in the range `0..1`.
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
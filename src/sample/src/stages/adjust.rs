@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::color::{hsv_to_rgb, rgb_to_hsv};
+use crate::core::{Sample, Stage};
+
+/// Hue, saturation, and luminance adjustments to apply to each LED.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Adjustment {
+    /// Multiplier applied to the saturation of each color.
+    pub saturation_gain: f32,
+    /// Degrees to rotate the hue of each color by.
+    pub hue_shift: f32,
+    /// Multiplier applied to the luminance (HSV value) of each color.
+    pub luminance_gain: f32,
+    /// Saturation (0 to 1) below which colors are faded towards `white`, reaching it entirely at
+    /// a saturation of 0.
+    pub min_saturation: f32,
+    /// The `[r, g, b]` color shown for fully unsaturated input, scaled by the input's luminance.
+    pub white: [u8; 3],
+}
+
+impl Default for Adjustment {
+    fn default() -> Self {
+        Self {
+            saturation_gain: 1f32,
+            hue_shift: 0f32,
+            luminance_gain: 1f32,
+            min_saturation: 0f32,
+            white: [u8::MAX; 3],
+        }
+    }
+}
+
+impl Adjustment {
+    /// Adjust a single `[r, g, b]` color with channels from 0 to 1.
+    fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let [hue, saturation, value] = rgb_to_hsv(rgb);
+        let saturation = (saturation * self.saturation_gain).clamp(0f32, 1f32);
+        let value = (value * self.luminance_gain).clamp(0f32, 1f32);
+        let adjusted = hsv_to_rgb([hue + self.hue_shift, saturation, value]);
+
+        if saturation >= self.min_saturation {
+            return adjusted;
+        }
+
+        let fade = 1f32 - saturation / self.min_saturation;
+        let mut out = [0f32; 3];
+        for (c, channel) in out.iter_mut().enumerate() {
+            let white = self.white[c] as f32 / u8::MAX as f32 * value;
+            *channel = adjusted[c] + (white - adjusted[c]) * fade;
+        }
+        out
+    }
+}
+
+/// Stage that applies an `Adjustment` to every LED, such as boosting the saturation of the often
+/// washed-out colors sampled from screen content.
+pub struct AdjustStage {
+    adjustment: Adjustment,
+}
+
+impl AdjustStage {
+    pub fn new(adjustment: Adjustment) -> Self {
+        Self { adjustment }
+    }
+
+    pub fn adjustment(&self) -> &Adjustment {
+        &self.adjustment
+    }
+
+    /// Replace the adjustment being applied.
+    pub fn set_adjustment(&mut self, adjustment: Adjustment) {
+        self.adjustment = adjustment;
+    }
+}
+
+impl Stage for AdjustStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        let max = u8::MAX as f32;
+        sample.map_rgb(|color| {
+            self.adjustment
+                .apply(color.map(|channel| channel / max))
+                .map(|channel| channel * max)
+        });
+        sample
+    }
+}
@@ -0,0 +1,123 @@
+use ls_screenshot::Screenshot;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::debug;
+
+use crate::color::luma;
+use crate::core::{Sample, Stage};
+
+/// Only every this many pixels of the screenshot are measured, which is plenty for an average
+/// over the whole screen.
+const LUMINANCE_STRIDE: usize = 16;
+
+/// Mapping from the average luminance of the screen (0 to 1) to the gain applied to LED
+/// brightness, given as `[luminance, gain]` points that are linearly interpolated between.
+/// Luminances outside the points use the gain of the nearest point.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrightnessCurve {
+    pub points: Vec<[f32; 2]>,
+}
+
+impl Default for BrightnessCurve {
+    /// Full brightness for dark scenes, dimming to 40% as the screen approaches full white.
+    fn default() -> Self {
+        Self {
+            points: vec![[0f32, 1f32], [0.3, 1f32], [1f32, 0.4]],
+        }
+    }
+}
+
+impl BrightnessCurve {
+    /// Get the gain for a screen luminance.
+    pub fn gain(&self, luminance: f32) -> f32 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 1f32,
+        };
+        if luminance <= first[0] {
+            return first[1];
+        }
+
+        for pair in self.points.windows(2) {
+            let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+            if luminance <= x1 {
+                let t = (luminance - x0) / (x1 - x0).max(f32::EPSILON);
+                return y0 + (y1 - y0) * t;
+            }
+        }
+        last[1]
+    }
+}
+
+/// Stage that scales the brightness of every LED according to how bright the screen is overall,
+/// so that bright desktop content doesn't light up the wall at full power while dark scenes still
+/// get some glow.  The screen luminance is smoothed over time, separately from any per-LED
+/// smoothing, so that brightness drifts slowly rather than pumping with the content.
+pub struct AdaptiveBrightnessStage {
+    curve: BrightnessCurve,
+    response_time: Duration,
+    luminance: Option<f32>,
+    last_time: Option<OffsetDateTime>,
+}
+
+impl AdaptiveBrightnessStage {
+    /// Create a new stage.  `response_time` is the time constant of the luminance smoothing, the
+    /// time taken to move about two thirds of the way to a new screen luminance.
+    pub fn new(curve: BrightnessCurve, response_time: Duration) -> Self {
+        Self {
+            curve,
+            response_time,
+            luminance: None,
+            last_time: None,
+        }
+    }
+
+    /// Measure the luminance of a newly captured screenshot.  This should be called with each
+    /// screenshot before the samples taken from it are processed.
+    pub fn observe(&mut self, screenshot: &Screenshot) {
+        let (total, count) = screenshot
+            .pixels
+            .iter()
+            .step_by(LUMINANCE_STRIDE)
+            .fold((0f32, 0usize), |(total, count), px| {
+                (total + luma(px), count + 1)
+            });
+        if count == 0 {
+            return;
+        }
+        let measured = total / count as f32;
+
+        let now = OffsetDateTime::now_utc();
+        self.luminance = Some(match (self.luminance, self.last_time) {
+            (Some(luminance), Some(last)) => {
+                let dt = (now - last).as_seconds_f32();
+                let alpha = 1f32 - (-dt / self.response_time.as_secs_f32().max(f32::EPSILON)).exp();
+                luminance + (measured - luminance) * alpha
+            }
+            _ => measured,
+        });
+        self.last_time = Some(now);
+    }
+
+    /// Smoothed luminance of the screen (0 to 1), if any screenshots have been observed.
+    pub fn luminance(&self) -> Option<f32> {
+        self.luminance
+    }
+
+    /// Gain currently being applied to LED brightness.
+    pub fn gain(&self) -> f32 {
+        self.luminance
+            .map_or(1f32, |luminance| self.curve.gain(luminance))
+    }
+}
+
+impl Stage for AdaptiveBrightnessStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        let gain = self.gain();
+        debug!(luminance = ?self.luminance, gain, "Applying adaptive brightness");
+        sample.map_rgb(|color| color.map(|channel| channel * gain));
+        sample
+    }
+}
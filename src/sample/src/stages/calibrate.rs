@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::core::{Sample, Stage};
+use crate::transition::{Easing, Transition};
+
+/// Color calibration settings for an LED strip, correcting for the difference between how a color
+/// appears on the monitor and how the strip renders it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalibrationProfile {
+    pub name: String,
+    /// Gamma exponent applied to the red, green, and blue channels.
+    pub gamma: [f32; 3],
+    /// Gain applied to the red, green, and blue channels, used to adjust the white point.
+    pub gain: [f32; 3],
+    /// Color correction matrix applied to each `[r, g, b]` color before gain and gamma, given as
+    /// rows such that `out[i] = sum(matrix[i][j] * in[j])`.
+    pub matrix: [[f32; 3]; 3],
+    /// Output value that the dimmest lit channel is raised to, for strips whose LEDs don't visibly
+    /// light at low values.  Channels that are fully off stay off.
+    pub min_brightness: u8,
+    /// Output value that a fully on channel is limited to.
+    pub max_brightness: u8,
+}
+
+impl Default for CalibrationProfile {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            gamma: [1f32; 3],
+            gain: [1f32; 3],
+            matrix: [[1f32, 0f32, 0f32], [0f32, 1f32, 0f32], [0f32, 0f32, 1f32]],
+            min_brightness: 0,
+            max_brightness: u8::MAX,
+        }
+    }
+}
+
+impl CalibrationProfile {
+    /// Create an identity profile with the given name, which leaves colors untouched.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Calibrate a single `[r, g, b]` color.
+    fn apply(&self, color: [f32; 3]) -> [f32; 3] {
+        let min = self.min_brightness as f32;
+        let max = self.max_brightness as f32;
+
+        let mut out = [0f32; 3];
+        for (c, row) in self.matrix.iter().enumerate() {
+            let mixed = row[0] * color[0] + row[1] * color[1] + row[2] * color[2];
+            let normalized = (mixed * self.gain[c] / u8::MAX as f32).clamp(0f32, 1f32);
+            out[c] = if normalized > 0f32 {
+                min + normalized.powf(self.gamma[c]) * (max - min)
+            } else {
+                0f32
+            };
+        }
+        out
+    }
+}
+
+/// Calibration profiles for each of the strips being driven, keyed by strip name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CalibrationProfiles {
+    strips: HashMap<String, CalibrationProfile>,
+}
+
+impl CalibrationProfiles {
+    pub fn new() -> Self {
+        Self {
+            strips: HashMap::new(),
+        }
+    }
+
+    /// Get the profile assigned to a strip, if any.
+    pub fn get(&self, strip: &str) -> Option<&CalibrationProfile> {
+        self.strips.get(strip)
+    }
+
+    /// Assign a profile to a strip, returning the profile previously assigned to it.
+    pub fn insert(
+        &mut self,
+        strip: &str,
+        profile: CalibrationProfile,
+    ) -> Option<CalibrationProfile> {
+        self.strips.insert(strip.to_string(), profile)
+    }
+}
+
+/// Stage that applies a `CalibrationProfile` to every LED.
+pub struct CalibrationStage {
+    profile: CalibrationProfile,
+    /// Profile being transitioned away from, if a transition is in progress.
+    previous: Option<(CalibrationProfile, Transition)>,
+}
+
+impl CalibrationStage {
+    pub fn new(profile: CalibrationProfile) -> Self {
+        Self {
+            profile,
+            previous: None,
+        }
+    }
+
+    pub fn profile(&self) -> &CalibrationProfile {
+        &self.profile
+    }
+
+    /// Replace the profile being applied.
+    pub fn set_profile(&mut self, profile: CalibrationProfile) {
+        self.profile = profile;
+        self.previous = None;
+    }
+
+    /// Replace the profile being applied, crossfading from the current profile over `duration`.
+    pub fn transition_to(
+        &mut self,
+        profile: CalibrationProfile,
+        duration: Duration,
+        easing: Easing,
+    ) {
+        let previous = std::mem::replace(&mut self.profile, profile);
+        self.previous = Some((previous, Transition::new(duration, easing)));
+    }
+
+    fn calibrate(profile: &CalibrationProfile, mut sample: Sample) -> Sample {
+        sample.map_rgb(|color| profile.apply(color));
+        sample
+    }
+}
+
+impl Stage for CalibrationStage {
+    fn process(&mut self, sample: Sample) -> Sample {
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|(_, transition)| transition.is_finished())
+        {
+            self.previous = None;
+        }
+
+        match &self.previous {
+            Some((previous, transition)) => {
+                let from = CalibrationStage::calibrate(previous, sample.clone());
+                let to = CalibrationStage::calibrate(&self.profile, sample);
+                transition.blend(&from, &to)
+            }
+            None => CalibrationStage::calibrate(&self.profile, sample),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use time::OffsetDateTime;
+use tracing::debug;
+
+use crate::core::{Sample, Stage};
+
+/// Weight given to each new frame interval when estimating the output frame rate.
+const RATE_ALPHA: f32 = 0.1;
+
+/// Stage that rounds the higher precision colors kept by earlier stages down to 8 bits using
+/// temporal dithering, carrying each LED's rounding error over into the next frame.  Over several
+/// frames the LED averages out to the precise color, smoothing out the visible steps between dim
+/// colors and keeping very dark colors from collapsing to off.
+///
+/// Dithering only blends into a steady color when frames are shown quickly enough, so below the
+/// minimum frame rate samples are passed through unchanged.  This stage should come last in the
+/// pipeline.
+pub struct DitherStage {
+    min_fps: f32,
+    /// Rounding error carried over for each channel of each LED.
+    error: Vec<[f32; 3]>,
+    /// Estimated time between frames, in seconds.
+    frame_interval: Option<f32>,
+    last_time: Option<OffsetDateTime>,
+}
+
+impl DitherStage {
+    /// Create a new dithering stage that is only active while frames are being output at
+    /// `min_fps` or faster.
+    pub fn new(min_fps: f32) -> Self {
+        Self {
+            min_fps,
+            error: Vec::new(),
+            frame_interval: None,
+            last_time: None,
+        }
+    }
+
+    /// Estimated rate at which frames are being output, in frames per second.
+    pub fn fps(&self) -> Option<f32> {
+        self.frame_interval
+            .map(|interval| 1f32 / interval.max(f32::EPSILON))
+    }
+
+    fn update_frame_rate(&mut self) {
+        let now = OffsetDateTime::now_utc();
+        if let Some(last) = self.last_time {
+            let interval = (now - last).as_seconds_f32();
+            self.frame_interval = Some(match self.frame_interval {
+                Some(estimate) => estimate + (interval - estimate) * RATE_ALPHA,
+                None => interval,
+            });
+        }
+        self.last_time = Some(now);
+    }
+}
+
+impl Stage for DitherStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        self.update_frame_rate();
+
+        let fast_enough = self.fps().is_some_and(|fps| fps >= self.min_fps);
+        if !fast_enough || sample.wide.is_none() {
+            self.error.clear();
+            return sample;
+        }
+
+        let colors = sample.colors();
+        if self.error.len() != colors.len() {
+            debug!(leds = colors.len(), "Resetting dither error");
+            self.error = vec![[0f32; 3]; colors.len()];
+        }
+
+        for ((px, color), error) in sample
+            .pixels
+            .iter_mut()
+            .zip(colors.iter())
+            .zip(self.error.iter_mut())
+        {
+            let mut out = [0u8; 3];
+            for c in 0..3 {
+                let wanted = color[c] + error[c];
+                let rounded = wanted.round().clamp(0f32, u8::MAX as f32);
+                error[c] = wanted - rounded;
+                out[c] = rounded as u8;
+            }
+            px.r = out[0];
+            px.g = out[1];
+            px.b = out[2];
+        }
+
+        // the dithered frame is final, so later consumers must not undo it with the precise colors
+        sample.wide = None;
+        sample
+    }
+}
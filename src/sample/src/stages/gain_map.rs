@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::core::{Sample, Stage};
+
+/// How the brightness lost to voltage drop grows with distance from the nearest injection point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Falloff {
+    Linear,
+    Quadratic,
+}
+
+/// Gain applied to each channel of each LED to make up for the brightness lost along the strip.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum GainMap {
+    /// `[r, g, b]` gains measured for each LED, in order along the strip.  LEDs beyond the end of
+    /// the list are left as is.
+    Measured(Vec<[f32; 3]>),
+    /// Losses modeled from where power is injected into the strip.
+    Model {
+        /// Indices of the LEDs that power is injected at.
+        injection_points: Vec<usize>,
+        /// Fraction (0 to 1) of the red, green, and blue brightness lost at the LED furthest from
+        /// any injection point.  Blue and green usually drop off sooner than red.
+        max_loss: [f32; 3],
+        falloff: Falloff,
+    },
+}
+
+impl GainMap {
+    /// Compute the gains for a strip of `leds` LEDs.
+    pub fn gains(&self, leds: usize) -> Vec<[f32; 3]> {
+        match self {
+            GainMap::Measured(gains) => (0..leds)
+                .map(|i| gains.get(i).copied().unwrap_or([1f32; 3]))
+                .collect(),
+            GainMap::Model {
+                injection_points,
+                max_loss,
+                falloff,
+            } => {
+                let distances: Vec<usize> = (0..leds)
+                    .map(|i| {
+                        injection_points
+                            .iter()
+                            .map(|point| i.abs_diff(*point))
+                            .min()
+                            .unwrap_or(i)
+                    })
+                    .collect();
+                let furthest = distances.iter().copied().max().unwrap_or(0).max(1);
+
+                distances
+                    .iter()
+                    .map(|distance| {
+                        let x = *distance as f32 / furthest as f32;
+                        let drop = match falloff {
+                            Falloff::Linear => x,
+                            Falloff::Quadratic => x * x,
+                        };
+                        max_loss.map(|loss| {
+                            let remaining = 1f32 - loss.clamp(0f32, 1f32) * drop;
+                            1f32 / remaining.max(f32::EPSILON)
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Stage that applies a per-LED gain map to make up for LEDs far from where power is injected
+/// being dimmer and redder than those close to it.  Compensated colors that would exceed full
+/// intensity are clipped.  This should come before `PowerLimitStage`, so that the current drawn
+/// by the compensated colors is what gets limited.
+pub struct GainMapStage {
+    map: GainMap,
+    gains: Vec<[f32; 3]>,
+}
+
+impl GainMapStage {
+    pub fn new(map: GainMap) -> Self {
+        Self {
+            map,
+            gains: Vec::new(),
+        }
+    }
+
+    /// Replace the gain map being applied.
+    pub fn set_map(&mut self, map: GainMap) {
+        self.map = map;
+        self.gains.clear();
+    }
+}
+
+impl Stage for GainMapStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        if self.gains.len() != sample.pixels.len() {
+            debug!(leds = sample.pixels.len(), "Computing per-LED gains");
+            self.gains = self.map.gains(sample.pixels.len());
+        }
+
+        let mut gains = self.gains.iter();
+        sample.map_rgb(|color| {
+            let gain = gains.next().copied().unwrap_or([1f32; 3]);
+            [0, 1, 2].map(|c| color[c] * gain[c])
+        });
+        sample
+    }
+}
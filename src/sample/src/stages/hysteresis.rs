@@ -0,0 +1,77 @@
+use std::time::Duration;
+use time::OffsetDateTime;
+
+use crate::color::{delta_e, rgb_to_lab};
+use crate::core::{Sample, Stage};
+
+/// Stage that holds the color of each LED until the sampled color moves perceptibly away from it,
+/// hiding the jitter of a few values per frame that video compression noise adds to averaged
+/// colors.  Small changes that persist are still shown once the maximum hold time passes, so that
+/// slow fades aren't frozen.
+pub struct HysteresisStage {
+    threshold: f32,
+    max_hold: Duration,
+    leds: Vec<HeldColor>,
+}
+
+impl HysteresisStage {
+    /// Create a new stage that holds colors until they change by more than `threshold`, measured
+    /// as CIE76 delta E in L*a*b* space, or until they have been held for `max_hold`.
+    pub fn new(threshold: f32, max_hold: Duration) -> Self {
+        Self {
+            threshold,
+            max_hold,
+            leds: Vec::new(),
+        }
+    }
+}
+
+impl Stage for HysteresisStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        let now = OffsetDateTime::now_utc();
+        let mut colors = sample.colors();
+        if self.leds.len() != colors.len() {
+            self.leds = colors
+                .iter()
+                .map(|color| HeldColor::new(*color, now))
+                .collect();
+            return sample;
+        }
+
+        for (led, color) in self.leds.iter_mut().zip(colors.iter_mut()) {
+            let changed = delta_e(led.lab, to_lab(color)) > self.threshold;
+            let expired = now - led.since >= self.max_hold;
+            if changed || expired {
+                *led = HeldColor::new(*color, now);
+            } else {
+                *color = led.color;
+            }
+        }
+
+        sample.set_colors(&colors);
+        sample
+    }
+}
+
+/// Color being held on a single LED.
+struct HeldColor {
+    /// Color, with channels from 0 to 255.
+    color: [f32; 3],
+    lab: [f32; 3],
+    /// Time the color started being held.
+    since: OffsetDateTime,
+}
+
+impl HeldColor {
+    fn new(color: [f32; 3], since: OffsetDateTime) -> Self {
+        Self {
+            color,
+            lab: to_lab(&color),
+            since,
+        }
+    }
+}
+
+fn to_lab(color: &[f32; 3]) -> [f32; 3] {
+    rgb_to_lab(color.map(|channel| channel / u8::MAX as f32))
+}
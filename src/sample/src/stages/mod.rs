@@ -0,0 +1,74 @@
+pub mod adjust;
+pub mod brightness;
+pub mod calibrate;
+pub mod dither;
+pub mod gain_map;
+pub mod hysteresis;
+pub mod power;
+pub mod resample;
+pub mod smooth;
+pub mod temperature;
+
+use crate::core::{Sample, Stage};
+
+/// Ordered collection of stages, each fed the output of the one before it.
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// Append a stage to the end of the pipeline.
+    pub fn push(&mut self, stage: Box<dyn Stage>) {
+        self.stages.push(stage);
+    }
+}
+
+impl Stage for Pipeline {
+    fn process(&mut self, sample: Sample) -> Sample {
+        self.stages
+            .iter_mut()
+            .fold(sample, |sample, stage| stage.process(sample))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+    use crate::core::Layout;
+    use adjust::{AdjustStage, Adjustment};
+    use ls_screenshot::FrameInfo;
+    use resample::{Interpolation, ResampleStage};
+    use smooth::{SmoothingMode, SmoothingStage};
+
+    #[test]
+    fn carries_frame_info_through_stages() {
+        let mut pipeline = Pipeline::new();
+        pipeline.push(Box::new(AdjustStage::new(Adjustment::default())));
+        pipeline.push(Box::new(ResampleStage::new(
+            Layout::new(7, 5),
+            Interpolation::Cubic,
+        )));
+        pipeline.push(Box::new(SmoothingStage::new(
+            SmoothingMode::Exponential { alpha: 0.5 },
+            1f32,
+        )));
+
+        let info = FrameInfo {
+            unchanged: true,
+            ..FrameInfo::new("test", 3)
+        };
+        let sample = Sample::new(vec![rgb(10, 20, 30); 10], 4, 3).with_info(info.clone());
+        let output = pipeline.process(sample);
+        assert_eq!(output.pixels.len(), 20);
+        assert_eq!(output.info.frame_id, 3);
+        assert_eq!(output.info.source, "test");
+        assert_eq!(output.info.captured, info.captured);
+        assert!(output.info.unchanged);
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::core::{Sample, Stage};
+
+/// Electrical characteristics of an LED strip and the supply powering it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerBudget {
+    /// Current drawn by the red, green, and blue channels of a single LED at full intensity, in mA.
+    pub channel_ma: [f32; 3],
+    /// Current drawn by a single LED with all channels off, in mA.
+    pub idle_ma: f32,
+    /// Maximum current the supply can provide to the strip, in mA.
+    pub limit_ma: f32,
+}
+
+impl Default for PowerBudget {
+    /// Typical WS2812 draw on a 2A supply.
+    fn default() -> Self {
+        Self {
+            channel_ma: [20f32; 3],
+            idle_ma: 1f32,
+            limit_ma: 2000f32,
+        }
+    }
+}
+
+impl PowerBudget {
+    /// Estimate the current drawn to display a frame, in mA, split into the portion drawn
+    /// regardless of color and the portion drawn by the lit channels.
+    fn estimate(&self, sample: &Sample) -> (f32, f32) {
+        let idle = self.idle_ma * sample.pixels.len() as f32;
+        let lit = sample
+            .colors()
+            .iter()
+            .map(|[r, g, b]| {
+                (r * self.channel_ma[0] + g * self.channel_ma[1] + b * self.channel_ma[2])
+                    / u8::MAX as f32
+            })
+            .sum();
+        (idle, lit)
+    }
+}
+
+/// Stage that estimates the current drawn by each frame and scales the whole frame down
+/// proportionally whenever it would exceed the supply's limit.
+pub struct PowerLimitStage {
+    budget: PowerBudget,
+}
+
+impl PowerLimitStage {
+    pub fn new(budget: PowerBudget) -> Self {
+        Self { budget }
+    }
+}
+
+impl Stage for PowerLimitStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        let (idle_ma, lit_ma) = self.budget.estimate(&sample);
+        let estimated_ma = idle_ma + lit_ma;
+        if estimated_ma <= self.budget.limit_ma {
+            debug!(estimated_ma, "Frame within power budget");
+            return sample;
+        }
+
+        let scale = ((self.budget.limit_ma - idle_ma) / lit_ma).clamp(0f32, 1f32);
+        let limited_ma = idle_ma + lit_ma * scale;
+        debug!(
+            estimated_ma,
+            limited_ma, scale, "Frame exceeds power budget, scaling down"
+        );
+
+        sample.map_rgb(|color| color.map(|channel| channel * scale));
+        sample
+    }
+}
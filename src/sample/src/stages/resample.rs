@@ -0,0 +1,207 @@
+use dxgcap::BGRA8;
+
+use crate::core::{Layout, Sample, Stage};
+
+/// Method used to compute colors that fall between the LEDs of the input sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    /// Catmull-Rom spline through the neighboring LEDs, giving smoother gradients than `Linear`.
+    Cubic,
+}
+
+/// Stage that maps a sample onto a different number of physical LEDs, such as when sampling at a
+/// coarse grid for performance.  Each edge of the input is stretched onto the matching edge of the
+/// output so that corners stay aligned.
+pub struct ResampleStage {
+    layout: Layout,
+    interpolation: Interpolation,
+}
+
+impl ResampleStage {
+    /// Create a new stage outputting samples with the given LED layout.
+    pub fn new(layout: Layout, interpolation: Interpolation) -> Self {
+        Self {
+            layout,
+            interpolation,
+        }
+    }
+
+    /// Position on the input ring, in LEDs from the top-left corner, that corresponds to output
+    /// LED `index`.
+    fn source_position(&self, input: &Layout, index: usize) -> f32 {
+        let out_corners = self.layout.corners();
+        let out_edges = self.layout.edge_lengths();
+        let in_corners = input.corners();
+        let in_edges = input.edge_lengths();
+
+        let edge = (0..4).rev().find(|e| out_corners[*e] <= index).unwrap();
+        let along = (index - out_corners[edge]) as f32 / out_edges[edge] as f32;
+        in_corners[edge] as f32 + along * in_edges[edge] as f32
+    }
+}
+
+impl Stage for ResampleStage {
+    fn process(&mut self, sample: Sample) -> Sample {
+        let input = sample.layout();
+        if input == self.layout {
+            return sample;
+        }
+
+        // interpolate the full precision colors so that they can still be dithered
+        let channels: Vec<[f32; 4]> = sample
+            .colors()
+            .iter()
+            .zip(sample.pixels.iter())
+            .map(|(color, px)| [color[0], color[1], color[2], px.a as f32])
+            .collect();
+        let outputs: Vec<[f32; 4]> = (0..self.layout.len())
+            .map(|i| {
+                let position = self.source_position(&input, i);
+                match self.interpolation {
+                    Interpolation::Linear => linear(&channels, position),
+                    Interpolation::Cubic => cubic(&channels, position),
+                }
+            })
+            .collect();
+
+        let pixels = outputs.iter().map(|channels| to_pixel(*channels)).collect();
+        let colors: Vec<[f32; 3]> = outputs
+            .iter()
+            .map(|channels| [channels[0], channels[1], channels[2]])
+            .collect();
+        let mut resampled =
+            Sample::new(pixels, self.layout.width, self.layout.height).with_info(sample.info);
+        resampled.set_colors(&colors);
+        resampled
+    }
+}
+
+fn to_pixel(channels: [f32; 4]) -> BGRA8 {
+    let clamp = |value: f32| value.round().clamp(0f32, u8::MAX as f32) as u8;
+    BGRA8 {
+        b: clamp(channels[2]),
+        g: clamp(channels[1]),
+        r: clamp(channels[0]),
+        a: clamp(channels[3]),
+    }
+}
+
+/// Get the `[r, g, b, a]` channels at `index` on the ring, wrapping around in either direction.
+fn ring(pixels: &[[f32; 4]], index: isize) -> [f32; 4] {
+    pixels[index.rem_euclid(pixels.len() as isize) as usize]
+}
+
+fn linear(pixels: &[[f32; 4]], position: f32) -> [f32; 4] {
+    let i = position.floor() as isize;
+    let t = position - position.floor();
+    let (p0, p1) = (ring(pixels, i), ring(pixels, i + 1));
+
+    let mut out = [0f32; 4];
+    for (c, value) in out.iter_mut().enumerate() {
+        *value = p0[c] + (p1[c] - p0[c]) * t;
+    }
+    out
+}
+
+fn cubic(pixels: &[[f32; 4]], position: f32) -> [f32; 4] {
+    let i = position.floor() as isize;
+    let t = position - position.floor();
+    let (p0, p1, p2, p3) = (
+        ring(pixels, i - 1),
+        ring(pixels, i),
+        ring(pixels, i + 1),
+        ring(pixels, i + 2),
+    );
+
+    let mut out = [0f32; 4];
+    for (c, value) in out.iter_mut().enumerate() {
+        *value = 0.5
+            * (2f32 * p1[c]
+                + (p2[c] - p0[c]) * t
+                + (2f32 * p0[c] - 5f32 * p1[c] + 4f32 * p2[c] - p3[c]) * t * t
+                + (3f32 * p1[c] - p0[c] - 3f32 * p2[c] + p3[c]) * t * t * t);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+
+    /// Sample where the red channel of each LED is its index, so that outputs can be traced back to
+    /// the input LEDs they came from.
+    fn indexed(layout: Layout) -> Sample {
+        let pixels = (0..layout.len()).map(|i| rgb(i as u8 * 10, 0, 0)).collect();
+        Sample::new(pixels, layout.width, layout.height)
+    }
+
+    fn resample(input: Sample, output: Layout, interpolation: Interpolation) -> Sample {
+        ResampleStage::new(output, interpolation).process(input)
+    }
+
+    #[test]
+    fn same_layout_passes_through() {
+        let layout = Layout::new(4, 3);
+        let output = resample(indexed(layout), layout, Interpolation::Cubic);
+        let reds: Vec<u8> = output.pixels.iter().map(|px| px.r).collect();
+        assert_eq!(reds, (0..10).map(|i| i * 10).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn corners_map_to_corners_when_upsampling() {
+        let (input, output) = (Layout::new(4, 3), Layout::new(7, 5));
+        for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+            let sample = resample(indexed(input), output, interpolation);
+            assert_eq!(sample.pixels.len(), output.len());
+            for (out, inp) in output.corners().iter().zip(input.corners().iter()) {
+                assert_eq!(
+                    sample.pixels[*out].r,
+                    *inp as u8 * 10,
+                    "{interpolation:?} corner {out} should match input corner {inp}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn corners_map_to_corners_when_downsampling() {
+        let (input, output) = (Layout::new(9, 6), Layout::new(5, 4));
+        for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+            let sample = resample(indexed(input), output, interpolation);
+            for (out, inp) in output.corners().iter().zip(input.corners().iter()) {
+                assert_eq!(sample.pixels[*out].r, *inp as u8 * 10);
+            }
+        }
+    }
+
+    #[test]
+    fn last_led_wraps_to_first() {
+        // the last output LED falls halfway between the last input LED and LED 0
+        let input = Layout::new(4, 3);
+        let mut pixels = vec![rgb(0, 0, 0); input.len()];
+        pixels[0] = rgb(255, 255, 255);
+        let sample = resample(
+            Sample::new(pixels, input.width, input.height),
+            Layout::new(7, 5),
+            Interpolation::Linear,
+        );
+        assert_eq!(sample.pixels.last().unwrap().r, 128);
+    }
+
+    #[test]
+    fn keeps_full_precision() {
+        let mut input = Sample::new(vec![rgb(0, 0, 0); 10], 4, 3);
+        input.set_colors(&[[100.25, 50.5, 10.75]; 10]);
+        for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+            let output = resample(input.clone(), Layout::new(7, 5), interpolation);
+            assert!(output.wide.is_some());
+            for color in output.colors() {
+                for (channel, expected) in color.iter().zip([100.25, 50.5, 10.75]) {
+                    assert!((channel - expected).abs() < 0.01, "got {channel}");
+                }
+            }
+        }
+    }
+}
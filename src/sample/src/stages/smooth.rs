@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::debug;
+
+use crate::core::{Sample, Stage};
+
+/// How the color of each LED approaches the color most recently sampled for it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum SmoothingMode {
+    /// Exponential moving average, where `alpha` is the weight (0 to 1) given to each new sample.
+    Exponential { alpha: f32 },
+    /// Linear interpolation towards each newly sampled color, arriving after `settle_time` once the
+    /// sampled color stops changing.
+    Settle { settle_time: Duration },
+}
+
+/// Stage that filters the color of each LED over time so that noisy content does not make the strip
+/// flicker.  Frames that differ from the previous frame by more than the scene cut threshold are
+/// passed through unfiltered so that the lights don't lag behind hard cuts.
+pub struct SmoothingStage {
+    mode: SmoothingMode,
+    scene_cut_threshold: f32,
+    leds: Vec<LedState>,
+    last_time: Option<OffsetDateTime>,
+}
+
+impl SmoothingStage {
+    /// Create a new smoothing stage.  `scene_cut_threshold` is the mean per-channel difference
+    /// between consecutive frames, as a fraction of full intensity (0 to 1), above which a frame is
+    /// considered a scene cut.
+    pub fn new(mode: SmoothingMode, scene_cut_threshold: f32) -> Self {
+        Self {
+            mode,
+            scene_cut_threshold,
+            leds: Vec::new(),
+            last_time: None,
+        }
+    }
+
+    /// Mean absolute per-channel difference between the incoming frame and the previous one, as a
+    /// fraction of full intensity.
+    fn frame_change(&self, colors: &[[f32; 3]]) -> f32 {
+        let total: f32 = self
+            .leds
+            .iter()
+            .zip(colors.iter())
+            .map(|(led, color)| {
+                (0..3)
+                    .map(|c| (color[c] - led.target[c]).abs())
+                    .sum::<f32>()
+            })
+            .sum();
+        total / (colors.len() * 3) as f32 / u8::MAX as f32
+    }
+}
+
+impl Stage for SmoothingStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        let now = OffsetDateTime::now_utc();
+        let dt = match self.last_time {
+            Some(last) => (now - last).as_seconds_f64() as f32,
+            None => 0f32,
+        };
+        self.last_time = Some(now);
+
+        let mut colors = sample.colors();
+        if self.leds.len() != colors.len() {
+            self.leds = colors.iter().map(LedState::new).collect();
+            return sample;
+        }
+
+        let change = self.frame_change(&colors);
+        if change > self.scene_cut_threshold {
+            debug!(change, "Scene cut detected, bypassing smoothing");
+            self.leds = colors.iter().map(LedState::new).collect();
+            return sample;
+        }
+
+        for (led, color) in self.leds.iter_mut().zip(colors.iter_mut()) {
+            let target = *color;
+            match self.mode {
+                SmoothingMode::Exponential { alpha } => {
+                    led.current = lerp(led.current, target, alpha);
+                }
+                SmoothingMode::Settle { settle_time } => {
+                    if target != led.target {
+                        led.ramp_from = led.current;
+                        led.ramp_elapsed = dt;
+                    } else {
+                        led.ramp_elapsed += dt;
+                    }
+                    let progress = if settle_time.is_zero() {
+                        1f32
+                    } else {
+                        (led.ramp_elapsed / settle_time.as_secs_f32()).min(1f32)
+                    };
+                    led.current = lerp(led.ramp_from, target, progress);
+                }
+            }
+            led.target = target;
+            *color = led.current;
+        }
+
+        sample.set_colors(&colors);
+        sample
+    }
+}
+
+/// Filter state for a single LED.
+struct LedState {
+    /// Color currently being output.
+    current: [f32; 3],
+    /// Most recently sampled color.
+    target: [f32; 3],
+    /// Color being output when the current settle ramp began.
+    ramp_from: [f32; 3],
+    /// Time spent on the current settle ramp, in seconds.
+    ramp_elapsed: f32,
+}
+
+impl LedState {
+    fn new(color: &[f32; 3]) -> Self {
+        let color = *color;
+        Self {
+            current: color,
+            target: color,
+            ramp_from: color,
+            ramp_elapsed: 0f32,
+        }
+    }
+}
+
+fn lerp(from: [f32; 3], to: [f32; 3], t: f32) -> [f32; 3] {
+    [
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+        from[2] + (to[2] - from[2]) * t,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+
+    fn frame(value: u8, leds: usize) -> Sample {
+        Sample::new(vec![rgb(value, value, value); leds], leds, 1)
+    }
+
+    fn reds(sample: &Sample) -> Vec<u8> {
+        sample.pixels.iter().map(|px| px.r).collect()
+    }
+
+    fn exponential(scene_cut_threshold: f32) -> SmoothingStage {
+        SmoothingStage::new(
+            SmoothingMode::Exponential { alpha: 0.25 },
+            scene_cut_threshold,
+        )
+    }
+
+    #[test]
+    fn first_frame_passes_through() {
+        let mut stage = exponential(1f32);
+        assert_eq!(reds(&stage.process(frame(200, 4))), vec![200; 4]);
+    }
+
+    #[test]
+    fn exponential_moves_towards_target() {
+        let mut stage = exponential(1f32);
+        stage.process(frame(0, 4));
+        assert_eq!(reds(&stage.process(frame(200, 4))), vec![50; 4]);
+        assert_eq!(reds(&stage.process(frame(200, 4))), vec![88; 4]);
+    }
+
+    #[test]
+    fn scene_cut_passes_through_unsmoothed() {
+        let mut stage = exponential(0.5);
+        stage.process(frame(0, 4));
+        assert_eq!(reds(&stage.process(frame(255, 4))), vec![255; 4]);
+
+        // smoothing resumes from the cut frame rather than the frame before it
+        assert_eq!(reds(&stage.process(frame(215, 4))), vec![245; 4]);
+    }
+
+    #[test]
+    fn change_below_threshold_is_smoothed() {
+        let mut stage = exponential(0.5);
+        stage.process(frame(0, 4));
+        assert_eq!(reds(&stage.process(frame(100, 4))), vec![25; 4]);
+    }
+
+    #[test]
+    fn led_count_change_resets() {
+        let mut stage = exponential(1f32);
+        stage.process(frame(0, 4));
+        assert_eq!(reds(&stage.process(frame(200, 6))), vec![200; 6]);
+    }
+
+    #[test]
+    fn zero_settle_time_is_immediate() {
+        let mut stage = SmoothingStage::new(
+            SmoothingMode::Settle {
+                settle_time: Duration::ZERO,
+            },
+            1f32,
+        );
+        stage.process(frame(0, 4));
+        assert_eq!(reds(&stage.process(frame(200, 4))), vec![200; 4]);
+    }
+}
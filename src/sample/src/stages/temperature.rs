@@ -0,0 +1,225 @@
+use chrono::{Local, Offset};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, UtcOffset};
+use tracing::{debug, warn};
+
+use crate::color::kelvin_to_gains;
+use crate::core::{Sample, Stage};
+use crate::transition::Easing;
+
+const MINUTES_PER_DAY: f32 = 24f32 * 60f32;
+
+/// Color temperature and brightness to use from a time of day until the start of the next period.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SchedulePeriod {
+    /// Local time of day that the period starts at.
+    pub hour: u8,
+    pub minute: u8,
+    /// Color temperature that white is shifted to, in Kelvin.
+    pub kelvin: f32,
+    /// Multiplier applied to LED brightness.
+    pub brightness: f32,
+}
+
+impl SchedulePeriod {
+    pub fn new(hour: u8, minute: u8, kelvin: f32, brightness: f32) -> Self {
+        Self {
+            hour,
+            minute,
+            kelvin,
+            brightness,
+        }
+    }
+
+    /// Time of day the period starts at, in minutes since midnight.
+    fn start(&self) -> f32 {
+        self.hour as f32 * 60f32 + self.minute as f32
+    }
+}
+
+/// Daily schedule of color temperatures, such as shifting to a warm, dim night mode late in the
+/// evening.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorSchedule {
+    pub periods: Vec<SchedulePeriod>,
+    /// Minutes taken to move from one period's settings to the next once it starts.
+    pub transition_minutes: f32,
+    pub easing: Easing,
+}
+
+impl Default for ColorSchedule {
+    /// Daylight white from 7:00, and a warm night mode at 40% brightness from 22:00.
+    fn default() -> Self {
+        Self {
+            periods: vec![
+                SchedulePeriod::new(7, 0, 6500f32, 1f32),
+                SchedulePeriod::new(22, 0, 3000f32, 0.4),
+            ],
+            transition_minutes: 30f32,
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl ColorSchedule {
+    /// Schedule that applies the same color temperature and brightness all day.
+    pub fn fixed(kelvin: f32, brightness: f32) -> Self {
+        Self {
+            periods: vec![SchedulePeriod::new(0, 0, kelvin, brightness)],
+            ..Self::default()
+        }
+    }
+
+    /// Get the color temperature and brightness for a local time of day, in minutes since
+    /// midnight.
+    pub fn at(&self, minutes: f32) -> (f32, f32) {
+        let mut periods: Vec<&SchedulePeriod> = self.periods.iter().collect();
+        periods.sort_by(|a, b| a.start().total_cmp(&b.start()));
+        if periods.is_empty() {
+            return (6500f32, 1f32);
+        }
+
+        // before the first period of the day, the last period from the day before is still active
+        let current = periods
+            .iter()
+            .rposition(|period| period.start() <= minutes)
+            .unwrap_or(periods.len() - 1);
+        let previous = (current + periods.len() - 1) % periods.len();
+        let (current, previous) = (periods[current], periods[previous]);
+
+        let elapsed = (minutes - current.start()).rem_euclid(MINUTES_PER_DAY);
+        let t = if self.transition_minutes <= 0f32 {
+            1f32
+        } else {
+            self.easing.apply(elapsed / self.transition_minutes)
+        };
+        (
+            previous.kelvin + (current.kelvin - previous.kelvin) * t,
+            previous.brightness + (current.brightness - previous.brightness) * t,
+        )
+    }
+}
+
+/// Stage that shifts the white point of every LED to a color temperature, and dims it, following a
+/// daily schedule in local time.
+pub struct ColorTemperatureStage {
+    schedule: ColorSchedule,
+}
+
+impl ColorTemperatureStage {
+    /// Create a new stage following `schedule`.
+    pub fn new(schedule: ColorSchedule) -> Self {
+        Self { schedule }
+    }
+
+    pub fn schedule(&self) -> &ColorSchedule {
+        &self.schedule
+    }
+
+    /// Replace the schedule being followed.
+    pub fn set_schedule(&mut self, schedule: ColorSchedule) {
+        self.schedule = schedule;
+    }
+
+    /// Look up the current offset of the local time zone, so that the schedule follows daylight
+    /// saving changes, falling back to UTC if it can't be represented.
+    fn local_offset() -> UtcOffset {
+        let seconds = Local::now().offset().fix().local_minus_utc();
+        UtcOffset::from_whole_seconds(seconds).unwrap_or_else(|err| {
+            warn!(
+                seconds,
+                "Unable to use local time zone offset, using UTC: {}", err
+            );
+            UtcOffset::UTC
+        })
+    }
+}
+
+impl Stage for ColorTemperatureStage {
+    fn process(&mut self, mut sample: Sample) -> Sample {
+        let now = OffsetDateTime::now_utc().to_offset(ColorTemperatureStage::local_offset());
+        let minutes = now.hour() as f32 * 60f32 + now.minute() as f32 + now.second() as f32 / 60f32;
+        let (kelvin, brightness) = self.schedule.at(minutes);
+        debug!(kelvin, brightness, "Applying color temperature");
+
+        let gains = kelvin_to_gains(kelvin).map(|gain| gain * brightness);
+        sample.map_rgb(|color| [0, 1, 2].map(|c| color[c] * gains[c]));
+        sample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_at(schedule: &ColorSchedule, minutes: f32, kelvin: f32, brightness: f32) {
+        let (actual_kelvin, actual_brightness) = schedule.at(minutes);
+        assert!(
+            (actual_kelvin - kelvin).abs() < 1f32 && (actual_brightness - brightness).abs() < 0.001,
+            "at {minutes} expected ({kelvin}, {brightness}), got ({actual_kelvin}, {actual_brightness})"
+        );
+    }
+
+    fn linear() -> ColorSchedule {
+        ColorSchedule {
+            easing: Easing::Linear,
+            ..ColorSchedule::default()
+        }
+    }
+
+    #[test]
+    fn midnight_uses_previous_days_last_period() {
+        assert_at(&linear(), 0f32, 3000f32, 0.4);
+    }
+
+    #[test]
+    fn before_first_period_uses_previous_days_last_period() {
+        assert_at(&linear(), 6f32 * 60f32 + 59f32, 3000f32, 0.4);
+    }
+
+    #[test]
+    fn settles_on_current_period() {
+        assert_at(&linear(), 12f32 * 60f32, 6500f32, 1f32);
+        assert_at(&linear(), 23f32 * 60f32, 3000f32, 0.4);
+    }
+
+    #[test]
+    fn transitions_from_previous_period() {
+        // halfway through the 30 minute transitions into day and into night
+        assert_at(&linear(), 7f32 * 60f32 + 15f32, 4750f32, 0.7);
+        assert_at(&linear(), 22f32 * 60f32 + 15f32, 4750f32, 0.7);
+    }
+
+    #[test]
+    fn transition_continues_past_midnight() {
+        let schedule = ColorSchedule {
+            periods: vec![
+                SchedulePeriod::new(8, 0, 6500f32, 1f32),
+                SchedulePeriod::new(23, 50, 2000f32, 0f32),
+            ],
+            transition_minutes: 20f32,
+            easing: Easing::Linear,
+        };
+        // midnight is halfway through the transition, which finishes 10 minutes later
+        assert_at(&schedule, 0f32, 4250f32, 0.5);
+        assert_at(&schedule, 10f32, 2000f32, 0f32);
+    }
+
+    #[test]
+    fn periods_are_sorted_by_start() {
+        let mut schedule = linear();
+        schedule.periods.reverse();
+        assert_at(&schedule, 0f32, 3000f32, 0.4);
+        assert_at(&schedule, 12f32 * 60f32, 6500f32, 1f32);
+    }
+
+    #[test]
+    fn empty_schedule_is_neutral() {
+        let schedule = ColorSchedule {
+            periods: Vec::new(),
+            ..ColorSchedule::default()
+        };
+        assert_at(&schedule, 600f32, 6500f32, 1f32);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+use crate::color::mix;
+use crate::core::Sample;
+
+/// Curve describing how a transition's blend progresses over its duration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    /// Start slowly and speed up towards the end.
+    EaseIn,
+    /// Start quickly and slow down towards the end.
+    EaseOut,
+    /// Start and end slowly, moving fastest in the middle.
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map linear progress through a transition (0 to 1) to the amount blended (0 to 1).
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0f32, 1f32);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2f32 - t),
+            Easing::EaseInOut => t * t * (3f32 - 2f32 * t),
+        }
+    }
+}
+
+/// Blend from one stream of samples to another over a fixed duration, started at the moment the
+/// transition is created.
+#[derive(Clone, Debug)]
+pub struct Transition {
+    duration: Duration,
+    easing: Easing,
+    start: OffsetDateTime,
+}
+
+impl Transition {
+    pub fn new(duration: Duration, easing: Easing) -> Self {
+        Self {
+            duration,
+            easing,
+            start: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Amount (0 to 1) that the incoming stream should currently be blended in by.
+    pub fn progress(&self) -> f32 {
+        let elapsed = (OffsetDateTime::now_utc() - self.start).as_seconds_f32();
+        self.easing
+            .apply(elapsed / self.duration.as_secs_f32().max(f32::EPSILON))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        OffsetDateTime::now_utc() - self.start >= self.duration
+    }
+
+    /// Blend the current frames of the outgoing (`from`) and incoming (`to`) streams.  Frames for
+    /// different numbers of LEDs can't be blended, so the incoming frame is used as is.
+    pub fn blend(&self, from: &Sample, to: &Sample) -> Sample {
+        if from.pixels.len() != to.pixels.len() {
+            return to.clone();
+        }
+
+        let t = self.progress();
+        let pixels = from
+            .pixels
+            .iter()
+            .zip(to.pixels.iter())
+            .map(|(from, to)| mix(from, to, t))
+            .collect();
+        let colors: Vec<[f32; 3]> = from
+            .colors()
+            .iter()
+            .zip(to.colors().iter())
+            .map(|(from, to)| [0, 1, 2].map(|c| from[c] + (to[c] - from[c]) * t))
+            .collect();
+
+        // blend the full precision colors so that dithering isn't lost during the transition
+        let mut blended = Sample::new(pixels, to.width, to.height).with_info(to.info.clone());
+        blended.set_colors(&colors);
+        blended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::rgb;
+
+    fn sample(color: f32, leds: usize) -> Sample {
+        let mut sample = Sample::new(vec![rgb(0, 0, 0); leds], 4, 3);
+        sample.set_colors(&vec![[color; 3]; leds]);
+        sample
+    }
+
+    fn halfway() -> Transition {
+        Transition {
+            duration: Duration::from_secs(1000),
+            easing: Easing::Linear,
+            start: OffsetDateTime::now_utc() - Duration::from_secs(500),
+        }
+    }
+
+    #[test]
+    fn blend_keeps_full_precision() {
+        let blended = halfway().blend(&sample(10.25, 10), &sample(20.25, 10));
+        assert!(blended.wide.is_some());
+        for color in blended.colors() {
+            for channel in color {
+                assert!((channel - 15.25).abs() < 0.05, "got {channel}");
+            }
+        }
+        assert_eq!(blended.pixels[0].r, 15);
+    }
+
+    #[test]
+    fn blend_of_different_led_counts_uses_incoming() {
+        let blended = halfway().blend(&sample(10.25, 8), &sample(20.25, 10));
+        assert_eq!(blended.pixels.len(), 10);
+        assert!((blended.colors()[0][0] - 20.25).abs() < 0.01);
+    }
+}
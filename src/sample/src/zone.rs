@@ -0,0 +1,125 @@
+use dxgcap::BGRA8;
+use ls_screenshot::Screenshot;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::average::{AveragingMode, DarkRejection};
+use crate::mask::ExclusionMask;
+use crate::rect::NormRect;
+use crate::region::{Region, RegionAverager};
+
+/// Named area of the screen to be sampled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    pub rect: NormRect,
+}
+
+impl Zone {
+    pub fn new(name: &str, rect: NormRect) -> Self {
+        Self {
+            name: name.to_string(),
+            rect,
+        }
+    }
+}
+
+/// Sampler that outputs the average color of each of a set of named zones of the screen, for lights
+/// that aren't part of the edge strip such as bias lights and lamps.  It can be run alongside an
+/// edge sampler on the same `Screenshot`.
+pub struct ZoneSampler {
+    zones: Vec<Zone>,
+    averager: RegionAverager,
+    regions: Option<Vec<Region>>,
+}
+
+impl ZoneSampler {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        Self {
+            zones,
+            averager: RegionAverager::new(),
+            regions: None,
+        }
+    }
+
+    /// Create a sampler with a single zone, named `screen`, covering the whole screen.
+    pub fn whole_screen() -> Self {
+        Self::new(vec![Zone::new(
+            "screen",
+            NormRect::new(0f32, 0f32, 1f32, 1f32),
+        )])
+    }
+
+    /// Create a sampler with zones named `left`, `center`, and `right` covering each third of the
+    /// screen.
+    pub fn thirds() -> Self {
+        let third = 1f32 / 3f32;
+        Self::new(vec![
+            Zone::new("left", NormRect::new(0f32, 0f32, third, 1f32)),
+            Zone::new("center", NormRect::new(third, 0f32, third, 1f32)),
+            Zone::new("right", NormRect::new(third * 2f32, 0f32, third, 1f32)),
+        ])
+    }
+
+    /// Set the color space that the pixels of each zone are averaged in.
+    pub fn with_averaging_mode(mut self, mode: AveragingMode) -> Self {
+        self.averager.options.mode = mode;
+        self
+    }
+
+    /// Ignore pixels darker than the rejection threshold when averaging each zone.
+    pub fn with_dark_rejection(mut self, rejection: DarkRejection) -> Self {
+        self.averager.options.dark_rejection = Some(rejection);
+        self
+    }
+
+    /// Weight each pixel by its luminance when averaging each zone.
+    pub fn with_luminance_weighting(mut self, enabled: bool) -> Self {
+        self.averager.options.luminance_weighted = enabled;
+        self
+    }
+
+    /// Only sample every `stride`th pixel across and down each zone, trading accuracy for speed.
+    /// The sampled pixels are shifted randomly each frame to avoid aliasing against regular
+    /// patterns on screen.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.averager.set_stride(stride);
+        self
+    }
+
+    /// Skip the areas of the screen covered by `mask` when averaging each zone.
+    pub fn with_mask(mut self, mask: ExclusionMask) -> Self {
+        self.set_mask(Some(mask));
+        self
+    }
+
+    /// Replace the exclusion mask applied to the screen, or remove it if `None`.
+    pub fn set_mask(&mut self, mask: Option<ExclusionMask>) {
+        self.averager.set_mask(mask);
+    }
+
+    /// Compute the average color of each zone, keyed by zone name.
+    pub fn sample(&mut self, screenshot: &Screenshot) -> HashMap<String, BGRA8> {
+        if self.regions.is_none() {
+            self.regions = Some(
+                self.zones
+                    .iter()
+                    .map(|zone| {
+                        let [start_x, end_x, start_y, end_y] =
+                            zone.rect.to_pixels(screenshot.width, screenshot.height);
+                        Region::new(start_x, end_x, start_y, end_y)
+                    })
+                    .collect(),
+            );
+        }
+
+        let colors = self
+            .averager
+            .average(screenshot, self.regions.as_ref().unwrap());
+        self.zones
+            .iter()
+            .map(|zone| zone.name.clone())
+            .zip(colors)
+            .collect()
+    }
+}
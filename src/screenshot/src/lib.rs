@@ -1,39 +1,89 @@
 #![forbid(unsafe_code)]
 
-use dxgcap::{BGRA8, DXGIManager};
+use dxgcap::{BGRA8, CaptureError, DXGIManager};
+use time::OffsetDateTime;
 
+/// Name of the capture backend that `Screenshotter` frames come from.
+const DXGI_SOURCE: &str = "dxgi";
+
+/// Information about a captured frame, carried along with everything derived from it so that the
+/// output can be traced back to the capture.
+#[derive(Clone, Debug)]
+pub struct FrameInfo {
+    /// Time the frame was captured.
+    pub captured: OffsetDateTime,
+    /// Number of the frame, counting up from 0 for each frame produced by the same source.
+    pub frame_id: u64,
+    /// Name of the capture backend or other source that produced the frame.
+    pub source: String,
+    /// Whether the frame's content is known to be unchanged since the previous frame from the same
+    /// source.
+    pub unchanged: bool,
+}
+
+impl FrameInfo {
+    /// Create the information for a frame produced now.
+    pub fn new(source: &str, frame_id: u64) -> FrameInfo {
+        FrameInfo {
+            captured: OffsetDateTime::now_utc(),
+            frame_id,
+            source: source.to_string(),
+            unchanged: false,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Screenshot {
     pub pixels: Vec<BGRA8>,
     pub width: usize,
     pub height: usize,
+    pub info: FrameInfo,
 }
 
 impl Screenshot {
-    fn new(pixels: Vec<BGRA8>, width: usize, height: usize) -> Screenshot {
+    fn new(pixels: Vec<BGRA8>, width: usize, height: usize, info: FrameInfo) -> Screenshot {
         Screenshot {
             pixels,
             width,
             height,
+            info,
         }
     }
 }
 
 pub struct Screenshotter {
     manager: DXGIManager,
+    next_frame_id: u64,
+    /// Most recently captured frame, returned again while the screen isn't updated.
+    last: Option<Screenshot>,
 }
 
 impl Screenshotter {
     pub fn new() -> anyhow::Result<Screenshotter> {
         Ok(Screenshotter {
             manager: DXGIManager::new(50).map_err(anyhow::Error::msg)?,
+            next_frame_id: 0,
+            last: None,
         })
     }
 
-    pub fn capture(&mut self) -> anyhow::Result<Screenshot> {
-        let ss = self
-            .manager
-            .capture_frame()
-            .map_err(|err| anyhow::Error::msg(format!("{err:?}")))?;
-        Ok(Screenshot::new(ss.0, ss.1.0, ss.1.1))
+    /// Capture the screen.  If nothing on the screen has been updated since the previous capture,
+    /// the previous frame is returned again with a new frame id and marked as unchanged.
+    pub fn capture(&mut self) -> anyhow::Result<&Screenshot> {
+        let info = FrameInfo::new(DXGI_SOURCE, self.next_frame_id);
+        match self.manager.capture_frame() {
+            Ok(ss) => self.last = Some(Screenshot::new(ss.0, ss.1.0, ss.1.1, info)),
+            Err(CaptureError::Timeout) if self.last.is_some() => {
+                let last = self.last.as_mut().unwrap();
+                last.info = FrameInfo {
+                    unchanged: true,
+                    ..info
+                };
+            }
+            Err(err) => return Err(anyhow::Error::msg(format!("{err:?}"))),
+        }
+        self.next_frame_id += 1;
+        Ok(self.last.as_ref().unwrap())
     }
 }